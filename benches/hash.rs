@@ -1,6 +1,9 @@
 //! Micro-benchmark confirming the custom `FxBuildHasher` is faster than the
 //! default `RandomState` (SipHash) for the transposition-table workload:
-//! `HashMap<(u64, u64), i32>` keyed by board bitmasks.
+//! `HashMap<(u64, u64), i32>` keyed by board bitmasks. It also measures the
+//! fixed-size `TranspositionTable` the engines use now against the Fx map, at
+//! the same number of entries (the table may evict some, so its hit rate is
+//! shown too).
 //!
 //! Run with: `cargo bench --bench hash`
 //! (declared with `harness = false`, so this is a plain `main`).
@@ -13,6 +16,7 @@ use std::time::Instant;
 
 use reversi::reversi::hash::FxBuildHasher;
 use reversi::reversi::rand::Xor128;
+use reversi::reversi::tt::TranspositionTable;
 
 // A full 64-bit value out of the 31-bit generator.
 fn rand_u64(rng: &mut Xor128) -> u64 {
@@ -69,6 +73,39 @@ fn bench<S: BuildHasher + Default + Clone>(
     (best, checksum)
 }
 
+// Like `bench`, on a preallocated `TranspositionTable` with room for every key
+// (allocated outside the timed region, as the engines do once per game).
+// Returns (elapsed, checksum, hit rate of the probes).
+fn bench_table(keys: &[(u64, u64)], probe_passes: usize, rounds: usize) -> (f64, u64, f64) {
+    let mut best = f64::INFINITY;
+    let mut checksum = 0u64;
+    let mut hits = 0usize;
+    for _ in 0..rounds {
+        let table = TranspositionTable::with_capacity(keys.len());
+        let start = Instant::now();
+
+        for (i, &(black, white)) in keys.iter().enumerate() {
+            table.store(black_box(black), black_box(white), (i % 16) as u8, i as u64);
+        }
+
+        let mut acc = 0i64;
+        hits = 0;
+        for _ in 0..probe_passes {
+            for &(black, white) in keys {
+                if let Some((v, _)) = table.probe(black_box(black), black_box(white)) {
+                    acc = acc.wrapping_add(v as i64);
+                    hits += 1;
+                }
+            }
+        }
+
+        let elapsed = start.elapsed().as_secs_f64();
+        checksum = checksum.wrapping_add(acc as u64);
+        best = best.min(elapsed);
+    }
+    (best, checksum, hits as f64 / (keys.len() * probe_passes) as f64)
+}
+
 fn main() {
     const N: usize = 200_000; // keys inserted
     const PROBE_PASSES: usize = 8; // full lookup sweeps
@@ -80,13 +117,16 @@ fn main() {
     // Warm up caches / allocator so the first hasher isn't unfairly penalised.
     black_box(bench::<RandomState>(&keys, 1, 1));
     black_box(bench::<FxBuildHasher>(&keys, 1, 1));
+    black_box(bench_table(&keys, 1, 1));
 
     let (sip_t, c1) = bench::<RandomState>(&keys, PROBE_PASSES, ROUNDS);
     let (fx_t, c2) = bench::<FxBuildHasher>(&keys, PROBE_PASSES, ROUNDS);
-    black_box((c1, c2));
+    let (tt_t, c3, tt_hits) = bench_table(&keys, PROBE_PASSES, ROUNDS);
+    black_box((c1, c2, c3));
 
     let sip_ns = sip_t / ops * 1e9;
     let fx_ns = fx_t / ops * 1e9;
+    let tt_ns = tt_t / ops * 1e9;
 
     println!("transposition-table hash benchmark");
     println!("  keys = {N}, probe passes = {PROBE_PASSES}, rounds = {ROUNDS} (best shown)");
@@ -99,4 +139,15 @@ fn main() {
     } else {
         println!("  => WARNING: FxHasher is NOT faster; revisit the hash mixing.");
     }
+
+    println!();
+    println!("  {:<16} {:>10.3} ms   {:>7.3} ns/op", "FxHasher map", fx_t * 1e3, fx_ns);
+    println!(
+        "  {:<16} {:>10.3} ms   {:>7.3} ns/op   ({:.1}% hits)",
+        "fixed TT",
+        tt_t * 1e3,
+        tt_ns,
+        tt_hits * 100.0
+    );
+    println!("  speedup: {:.2}x", fx_t / tt_t);
 }
@@ -20,8 +20,8 @@
 
 use std::time::Instant;
 
-use reversi::reversi::bitboard::{legal_moves, Board, Mask};
-use reversi::reversi::gm::{GameManager, Winner};
+use reversi::reversi::bitboard::Mask;
+use reversi::reversi::gm::{GameManager, GameResult, Turn, Winner};
 use reversi::reversi::player::alphabeta::AlphaBetaSearchPlayer;
 use reversi::reversi::player::alphabeta2::AlphaBeta2Player;
 use reversi::reversi::player::alphabeta3::AlphaBeta3Player;
@@ -29,9 +29,10 @@ use reversi::reversi::player::alphabeta4::AlphaBeta4Player;
 use reversi::reversi::player::alphabeta42::AlphaBeta42Player;
 use reversi::reversi::player::alphabeta5::AlphaBeta5Player;
 use reversi::reversi::player::best::BestAiPlayer;
+use reversi::reversi::player::level::LevelPlayer;
+use reversi::reversi::player::mcts::MctsPlayer;
 use reversi::reversi::player::random::RandomPlayer;
 use reversi::reversi::player::Player;
-use reversi::reversi::rand::Xor128;
 
 // Games per ordered pair in the round-robin league (so each unordered pair
 // plays LEAGUE_GAMES as first + LEAGUE_GAMES as second = 2 * LEAGUE_GAMES total).
@@ -47,6 +48,15 @@ const DUEL_GAMES: u32 = 50;
 // opening per seed decorrelates the games and gives a real strength signal.
 const OPENING_PLIES: u32 = 8;
 
+// Playout length of "mcts-eval" before it scores the position with AB5's
+// evaluation.
+const MCTS_CUTOFF_PLIES: u32 = 8;
+
+// Iterations of "mcts-fast", the MCTS entry of the league: a tenth of the
+// default budget, so it keeps up with the other fast engines. `--duel` can
+// still pit the full "mcts" against anyone.
+const MCTS_LEAGUE_ITERATIONS: u32 = 2_000;
+
 // `fn` pointers (Send + Copy) so game specs move cheaply across worker threads.
 type Factory = fn(u32) -> Box<dyn Player>;
 
@@ -60,10 +70,24 @@ const PLAYERS: &[(&str, Factory)] = &[
     ("ab42", |seed| Box::new(AlphaBeta42Player::new(seed))),
     ("ab5", |seed| Box::new(AlphaBeta5Player::new(seed))),
     ("best", |seed| Box::new(BestAiPlayer::new(seed))),
+    ("mcts", |seed| Box::new(MctsPlayer::new(seed))),
+    ("mcts-fast", |seed| Box::new(MctsPlayer::with_iterations(seed, MCTS_LEAGUE_ITERATIONS))),
+    ("mcts-eval", |seed| {
+        let mut player = MctsPlayer::new(seed);
+        player.set_playout_cutoff(Some(MCTS_CUTOFF_PLIES));
+        Box::new(player)
+    }),
+    ("level1", |seed| Box::new(LevelPlayer::new(seed, 1))),
+    ("level2", |seed| Box::new(LevelPlayer::new(seed, 2))),
+    ("level3", |seed| Box::new(LevelPlayer::new(seed, 3))),
+    ("level4", |seed| Box::new(LevelPlayer::new(seed, 4))),
+    ("level5", |seed| Box::new(LevelPlayer::new(seed, 5))),
+    ("level6", |seed| Box::new(LevelPlayer::new(seed, 6))),
+    ("level7", |seed| Box::new(LevelPlayer::new(seed, 7))),
 ];
 
 // The fast engines that play the round-robin league (names into PLAYERS).
-const LEAGUE_ROSTER: &[&str] = &["ab", "ab2", "ab3", "ab42", "ab5"];
+const LEAGUE_ROSTER: &[&str] = &["ab", "ab2", "ab3", "ab42", "ab5", "mcts-fast"];
 
 fn lookup(name: &str) -> (&'static str, Factory) {
     match PLAYERS.iter().find(|(n, _)| *n == name) {
@@ -148,15 +172,44 @@ enum Outcome {
     Draw,
 }
 
-fn play_league(engines: &[(&'static str, Factory)], spec: Spec) -> Outcome {
+// Moves, seconds and reported search nodes of one engine, summed over games.
+#[derive(Clone, Copy, Default)]
+struct Effort {
+    moves: u32,
+    secs: f64,
+    nodes: u64,
+}
+
+impl Effort {
+    // `turn`'s share of the plies of `result` (passes not counted).
+    fn of(result: &GameResult, turn: Turn) -> Effort {
+        let mut effort = Effort::default();
+        for ply in result.plies.iter().filter(|ply| ply.turn == turn && ply.mov.is_some()) {
+            effort.moves += 1;
+            effort.secs += ply.time.map_or(0.0, |time| time.as_secs_f64());
+            effort.nodes += ply.search.as_ref().map_or(0, |info| info.nodes);
+        }
+        effort
+    }
+
+    fn add(&mut self, other: Effort) {
+        self.moves += other.moves;
+        self.secs += other.secs;
+        self.nodes += other.nodes;
+    }
+}
+
+fn play_league(engines: &[(&'static str, Factory)], spec: Spec) -> (Outcome, Effort, Effort) {
     // Distinct seed streams so the two engines never share randomness.
     let black = engines[spec.black].1(spec.seed);
     let white = engines[spec.white].1(spec.seed.wrapping_add(1_000_000));
-    match GameManager::new(black, white).playout().winner {
+    let result = GameManager::new(black, white).playout();
+    let outcome = match result.winner {
         Winner::Black => Outcome::Black,
         Winner::White => Outcome::White,
         Winner::Draw => Outcome::Draw,
-    }
+    };
+    (outcome, Effort::of(&result, Turn::Black), Effort::of(&result, Turn::White))
 }
 
 fn run_league() {
@@ -183,8 +236,11 @@ fn run_league() {
 
     // head[i][j] = (wins, losses, draws) of engine i vs engine j (both colours).
     let mut head = vec![vec![(0u32, 0u32, 0u32); n]; n];
-    for (spec, outcome) in results {
+    let mut effort = vec![Effort::default(); n];
+    for (spec, (outcome, black_effort, white_effort)) in results {
         let (b, w) = (spec.black, spec.white);
+        effort[b].add(black_effort);
+        effort[w].add(white_effort);
         match outcome {
             Outcome::Black => {
                 head[b][w].0 += 1;
@@ -243,10 +299,26 @@ fn run_league() {
     }
     println!();
 
-    // Final standings.
-    println!("{:<10} {:>4} {:>4} {:>4} {:>7}", "engine", "W", "L", "D", "pts");
+    // Final standings, with the average time and reported nodes per move.
+    println!(
+        "{:<10} {:>4} {:>4} {:>4} {:>7} {:>9} {:>10}",
+        "engine", "W", "L", "D", "pts", "ms/move", "nodes/move"
+    );
     for (i, w, l, d, pts) in standings.iter() {
-        println!("{:<10} {:>4} {:>4} {:>4} {:>7.1}", engines[*i].0, w, l, d, pts);
+        let Effort { moves, secs, nodes } = effort[*i];
+        let moves = moves.max(1) as f64;
+        let nodes =
+            if nodes == 0 { "-".to_string() } else { format!("{:.0}", nodes as f64 / moves) };
+        println!(
+            "{:<10} {:>4} {:>4} {:>4} {:>7.1} {:>9.2} {:>10}",
+            engines[*i].0,
+            w,
+            l,
+            d,
+            pts,
+            secs * 1000.0 / moves,
+            nodes
+        );
     }
 }
 
@@ -266,60 +338,23 @@ struct GameOut {
     margin: i32,
 }
 
-fn pick_random(mask: Mask, rng: &mut Xor128) -> Mask {
-    let count = mask.count_ones();
-    let mut k = rng.next() % count;
-    let mut m = mask;
-    loop {
-        let bit = m & m.wrapping_neg();
-        if k == 0 {
-            return bit;
-        }
-        k -= 1;
-        m &= m - 1;
-    }
-}
-
 fn play_duel(a: Factory, b: Factory, spec: DuelSpec) -> GameOut {
+    // The first OPENING_PLIES moves are random (identical for both games of a
+    // seed pair), then the players take over.
+    let mut opening = GameManager::new(
+        Box::new(RandomPlayer::new(spec.seed ^ 0x9E37_79B9)),
+        Box::new(RandomPlayer::new(spec.seed ^ 0x7F4A_7C15)),
+    );
+    opening.set_stop_empties(Some(60 - OPENING_PLIES));
+    let opening: Vec<Option<Mask>> = opening.playout().plies.iter().map(|ply| ply.mov).collect();
+
     // Distinct seed streams so the two engines never share randomness.
-    let mut pa = a(spec.seed);
-    let mut pb = b(spec.seed.wrapping_add(1_000_000));
-    let mut rng = Xor128::from_seed(spec.seed ^ 0x9E37_79B9);
-
-    // Drive the game manually so the first OPENING_PLIES moves are random
-    // (identical for both games of a seed pair), then the players take over.
-    let mut board = Board::new();
-    let mut turn_black = true;
-    let mut ply = 0u32;
-    let mut passes = 0;
-    loop {
-        // The mover always sees the board from the "black to move" perspective.
-        let view = if turn_black { board.clone() } else { board.switch() };
-        let lm = legal_moves(view.0, view.1);
-        if lm == 0 {
-            passes += 1;
-            if passes == 2 {
-                break;
-            }
-            turn_black = !turn_black;
-            continue;
-        }
-        passes = 0;
-
-        let mov = if ply < OPENING_PLIES {
-            pick_random(lm, &mut rng)
-        } else if turn_black == spec.a_black {
-            pa.next(&view).expect("legal move exists")
-        } else {
-            pb.next(&view).expect("legal move exists")
-        };
-
-        board = if turn_black { board.flip(mov) } else { board.switch().flip(mov).switch() };
-        turn_black = !turn_black;
-        ply += 1;
-    }
+    let pa = a(spec.seed);
+    let pb = b(spec.seed.wrapping_add(1_000_000));
+    let (black, white) = if spec.a_black { (pa, pb) } else { (pb, pa) };
+    let result = GameManager::with_opening(black, white, &opening).unwrap().playout();
 
-    let (black, white) = board.count();
+    let (black, white) = result.disks;
     let (my, opp) = if spec.a_black { (black, white) } else { (white, black) };
     let margin = my as i32 - opp as i32;
     GameOut { win: margin.signum(), margin }
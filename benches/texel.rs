@@ -0,0 +1,40 @@
+//! Texel-style fit of the linear evaluation weights (see `reversi::tuner`).
+//!
+//! Generates labelled positions by `AlphaBeta5` self-play (solved exactly from
+//! 14 empties), then fits the `AlphaBeta5` and `AlphaBeta4` weights to them and
+//! prints each fit as a `PhaseWeights` literal for review, with the prediction
+//! error before and after. Nothing is written back into the engines: paste a
+//! fit over the defaults only once it also wins the duel benches.
+//!
+//! Run with: `cargo bench --bench texel`  (harness = false).
+
+use std::time::Instant;
+
+use reversi::reversi::player::{alphabeta4, alphabeta5};
+use reversi::reversi::tuner::{tune, LinearWeights, SelfPlay};
+
+const ITERATIONS: usize = 500;
+
+fn main() {
+    let t0 = Instant::now();
+    let samples = SelfPlay::default().generate();
+    println!("{} samples in {:.1}s\n", samples.len(), t0.elapsed().as_secs_f64());
+
+    let fits = [
+        (
+            "AlphaBeta5",
+            tune(&samples, LinearWeights::from(alphabeta5::tuned_weights()), ITERATIONS),
+        ),
+        (
+            "AlphaBeta4",
+            tune(&samples, LinearWeights::from(alphabeta4::PhaseWeights::default()), ITERATIONS),
+        ),
+    ];
+    for (name, report) in fits {
+        println!(
+            "{name}: mse {:.2} -> {:.2} disks², {:.4} disks per unit",
+            report.error_before, report.error_after, report.scale
+        );
+        println!("{}\n", report.weights);
+    }
+}
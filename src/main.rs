@@ -1,25 +1,38 @@
+use reversi::reversi::bitboard::coordinate;
 use reversi::reversi::gm;
+use reversi::reversi::notation::Position;
+use reversi::reversi::perft::{divide, perft};
 use reversi::reversi::player;
 use reversi::reversi::player::alphabeta::AlphaBetaSearchPlayer;
 use reversi::reversi::player::cli::HumanPlayer;
+use reversi::reversi::player::level::{LevelPlayer, LEVELS};
 use reversi::reversi::player::random::RandomPlayer;
 use reversi::reversi::util;
 use std::io::{stdout, Write};
+use std::time::Instant;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("perft") {
+        run_perft(&args[1..]);
+        return;
+    }
+
     println!("choose players.");
     println!("  a : AI (alpha-beta search, default)");
     println!("  b : random");
     println!("  c : human (from keyboard)");
+    println!("  d : AI (adjustable strength, level 1-{})", LEVELS.len());
 
     let labels = ["black (first)", "white (second)"];
     let mut players = Vec::new();
     for label in labels.iter() {
-        print!("{} player? [A/b/c]: ", label);
+        print!("{} player? [A/b/c/d]: ", label);
         stdout().flush().unwrap();
         let player = match util::read_one_char().and_then(|a| a.to_lowercase().next()) {
             Some('b') => Box::new(RandomPlayer::new(28)) as Box<dyn player::Player>,
             Some('c') => Box::new(HumanPlayer::new()) as Box<dyn player::Player>,
+            Some('d') => Box::new(LevelPlayer::new(28, read_level())) as Box<dyn player::Player>,
             _ => Box::new(AlphaBetaSearchPlayer::new(28)) as Box<dyn player::Player>,
         };
         println!("selected {}", player.name());
@@ -30,3 +43,65 @@ fn main() {
     let white = players.swap_remove(0);
     gm::GameManager::new(black, white).playout_verbose();
 }
+
+// Asks for a level until a valid one is given.
+fn read_level() -> usize {
+    loop {
+        print!("level? [1-{}]: ", LEVELS.len());
+        stdout().flush().unwrap();
+        match util::read_one_char().and_then(|c| c.to_digit(10)) {
+            Some(level) if (1..=LEVELS.len()).contains(&(level as usize)) => return level as usize,
+            _ => println!("no such level."),
+        }
+    }
+}
+
+// `main perft <depth> [divide] [<position>]`: perft of `position` (the opening
+// by default, see `notation`) for every depth up to `depth`, or split by the
+// first move with `divide`.
+fn run_perft(args: &[String]) {
+    let usage = || -> ! {
+        eprintln!("usage: main perft <depth> [divide] [<position>]");
+        std::process::exit(2);
+    };
+    let depth = match args.first().and_then(|d| d.parse::<u32>().ok()) {
+        Some(depth) if depth > 0 => depth,
+        _ => usage(),
+    };
+    let divide_first = args.get(1).map(String::as_str) == Some("divide");
+    let rest = &args[if divide_first { 2 } else { 1 }..];
+    let position = if rest.is_empty() {
+        Position::new()
+    } else {
+        match rest.join(" ").parse::<Position>() {
+            Ok(position) => position,
+            Err(e) => {
+                eprintln!("bad position: {e}");
+                usage();
+            }
+        }
+    };
+    let board = position.to_move();
+    if divide_first {
+        let mut total = 0;
+        for (mov, leaves) in divide(&board, depth) {
+            let name = match mov {
+                Some(mov) => {
+                    let (r, c) = coordinate(mov);
+                    util::position_to_name(r, c)
+                }
+                None => "pass".to_string(),
+            };
+            println!("{name} {leaves}");
+            total += leaves;
+        }
+        println!("total {total}");
+        return;
+    }
+    for d in 1..=depth {
+        let start = Instant::now();
+        let leaves = perft(&board, d);
+        let secs = start.elapsed().as_secs_f64();
+        println!("perft({d}) = {leaves} ({secs:.3}s, {:.1} Mleaves/s)", leaves as f64 / secs / 1e6);
+    }
+}
@@ -1,11 +1,23 @@
 pub mod asciiboard;
 pub mod bitboard;
+pub mod book;
+pub mod ggf;
 pub mod gm;
 pub mod hash;
+pub mod notation;
+pub mod pattern;
+pub mod perft;
 pub mod player;
+#[cfg(test)]
+pub mod playout;
 pub mod rand;
+pub mod record;
 pub mod sprint;
+pub mod transcript;
+pub mod tt;
+pub mod tuner;
 pub mod util;
+pub mod wthor;
 
 /// Height of board.
 pub const H: usize = 8;
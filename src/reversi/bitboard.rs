@@ -8,7 +8,7 @@ pub type Mask = u64;
 /// self.0 represents the places of black disks.
 /// The i-th bit of self.0 is positive (one) iff. cell (i/H, i%H) has a black disk.
 /// The same for self.1 for white.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Board(pub Mask, pub Mask); // black, white
 
 /// Vestigial: this used to carry the per-rotation partial masks consumed by the
@@ -70,6 +70,105 @@ impl Board {
     pub fn flip_with_hints(&self, mov: Mask, _hints: &ValidMaskParts) -> Board {
         self.flip(mov)
     }
+
+    /// Returns the board with both colours mapped by `symmetry`.
+    #[inline]
+    pub fn transform(&self, symmetry: Symmetry) -> Board {
+        Board(symmetry.apply(self.0), symmetry.apply(self.1))
+    }
+
+    /// Returns the canonical form of the board, the least of its 8 symmetric
+    /// images ordered by `(black, white)`, together with the symmetry that maps
+    /// this board to it. Symmetric positions share one canonical form; map a
+    /// move or board found for it back with `symmetry.inverse()`.
+    pub fn canonical(&self) -> (Board, Symmetry) {
+        Symmetry::ALL
+            .iter()
+            .map(|&symmetry| (self.transform(symmetry), symmetry))
+            .min_by_key(|(board, _)| (board.0, board.1))
+            .unwrap()
+    }
+}
+
+/// One of the 8 symmetries of the board (the dihedral group of the square):
+/// the identity, three rotations and four mirrors. Rotations are clockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Upside down: (r, c) -> (7 - r, c).
+    FlipVertical,
+    /// Left to right: (r, c) -> (r, 7 - c).
+    FlipHorizontal,
+    /// About the A1-H8 diagonal: (r, c) -> (c, r).
+    FlipDiagonal,
+    /// About the H1-A8 diagonal: (r, c) -> (7 - c, 7 - r).
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipVertical,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// Maps every cell of `mask`.
+    #[inline]
+    pub fn apply(self, mask: Mask) -> Mask {
+        match self {
+            Symmetry::Identity => mask,
+            Symmetry::Rotate90 => mirror_horizontal(transpose(mask)),
+            Symmetry::Rotate180 => mask.reverse_bits(),
+            Symmetry::Rotate270 => transpose(mask).swap_bytes(),
+            Symmetry::FlipVertical => mask.swap_bytes(),
+            Symmetry::FlipHorizontal => mirror_horizontal(mask),
+            Symmetry::FlipDiagonal => transpose(mask),
+            Symmetry::FlipAntiDiagonal => transpose(mask).reverse_bits(),
+        }
+    }
+
+    /// The symmetry that undoes this one.
+    pub fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => other,
+        }
+    }
+}
+
+/// Reverses the cells of every row: (r, c) -> (r, 7 - c).
+#[inline]
+fn mirror_horizontal(x: Mask) -> Mask {
+    const K1: Mask = 0x5555555555555555;
+    const K2: Mask = 0x3333333333333333;
+    const K4: Mask = 0x0f0f0f0f0f0f0f0f;
+    let x = ((x >> 1) & K1) | ((x & K1) << 1);
+    let x = ((x >> 2) & K2) | ((x & K2) << 2);
+    ((x >> 4) & K4) | ((x & K4) << 4)
+}
+
+/// Swaps rows and columns: (r, c) -> (c, r), by three delta swaps.
+#[inline]
+fn transpose(x: Mask) -> Mask {
+    const K1: Mask = 0x5500550055005500;
+    const K2: Mask = 0x3333000033330000;
+    const K4: Mask = 0x0f0f0f0f00000000;
+    let mut x = x;
+    let t = K4 & (x ^ (x << 28));
+    x ^= t ^ (t >> 28);
+    let t = K2 & (x ^ (x << 14));
+    x ^= t ^ (t >> 14);
+    let t = K1 & (x ^ (x << 7));
+    x ^ t ^ (t >> 7)
 }
 
 /// Interior propagator mask: both file A (col 0) and file H (col 7) cleared, so
@@ -148,6 +247,81 @@ pub fn flip_disks(black: Mask, white: Mask, mov: Mask) -> Mask {
         | dir!(h, >>, 7)
 }
 
+// Zobrist keys: one random word per (square, colour) and one for "white to
+// move", generated at compile time by SplitMix64 from a fixed seed so hashes are
+// stable across runs and builds (they may be stored in books and databases).
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn zobrist_keys() -> ([[u64; 64]; 2], u64) {
+    let mut keys = [[0; 64]; 2];
+    let mut state: u64 = 0x0123_4567_89AB_CDEF;
+    let mut color = 0;
+    while color < 2 {
+        let mut square = 0;
+        while square < 64 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys[color][square] = key;
+            square += 1;
+        }
+        color += 1;
+    }
+    (keys, splitmix64(state).1)
+}
+
+const ZOBRIST: ([[u64; 64]; 2], u64) = zobrist_keys();
+const ZOBRIST_BLACK: [u64; 64] = ZOBRIST.0[0];
+const ZOBRIST_WHITE: [u64; 64] = ZOBRIST.0[1];
+
+/// Zobrist component of the side to move, included iff white is to move.
+pub const ZOBRIST_WHITE_TO_MOVE: u64 = ZOBRIST.1;
+
+/// Zobrist hash of `board` (black, white) with the given side to move: the xor
+/// of a fixed random key per disk (by square and colour), and
+/// `ZOBRIST_WHITE_TO_MOVE` when white is to move. Computed from scratch; along a
+/// game keep it up to date with `zobrist_play` and `zobrist_pass` instead.
+pub fn zobrist(board: &Board, black_to_move: bool) -> u64 {
+    let mut hash = if black_to_move { 0 } else { ZOBRIST_WHITE_TO_MOVE };
+    for (mask, keys) in [(board.0, &ZOBRIST_BLACK), (board.1, &ZOBRIST_WHITE)] {
+        let mut m = mask;
+        while m != 0 {
+            hash ^= keys[m.trailing_zeros() as usize];
+            m &= m - 1;
+        }
+    }
+    hash
+}
+
+/// `hash` after the side to move (black iff `black_to_move`) places a disk at
+/// `mov`, turning over `flips` (as returned by `flip_disks`). The side to move
+/// passes to the opponent. Costs one xor per flipped disk.
+#[inline]
+pub fn zobrist_play(hash: u64, black_to_move: bool, mov: Mask, flips: Mask) -> u64 {
+    let square = mov.trailing_zeros() as usize;
+    let mut hash = hash
+        ^ ZOBRIST_WHITE_TO_MOVE
+        ^ if black_to_move { ZOBRIST_BLACK[square] } else { ZOBRIST_WHITE[square] };
+    let mut m = flips;
+    while m != 0 {
+        let square = m.trailing_zeros() as usize;
+        hash ^= ZOBRIST_BLACK[square] ^ ZOBRIST_WHITE[square];
+        m &= m - 1;
+    }
+    hash
+}
+
+/// `hash` after the side to move passes.
+#[inline]
+pub fn zobrist_pass(hash: u64) -> u64 {
+    hash ^ ZOBRIST_WHITE_TO_MOVE
+}
+
 /// Put disk in the cell at (r, c) cell.
 #[inline]
 pub fn put(mask: Mask, r: usize, c: usize) -> Mask {
@@ -311,4 +485,112 @@ mod tests {
         // Sanity: we actually exercised a large number of distinct positions.
         assert!(checked_positions > 5000, "too few positions checked: {checked_positions}");
     }
+
+    // The incrementally updated hash must always equal the one computed from
+    // scratch, through moves and passes, with the board kept in black/white
+    // order.
+    #[test]
+    fn incremental_zobrist_matches_full_hash() {
+        let mut rng = Xor128::from_seed(777);
+        for _ in 0..100 {
+            let mut board = Board::new();
+            let mut black_to_move = true;
+            let mut hash = zobrist(&board, black_to_move);
+            let mut passed = false;
+            loop {
+                let (me, opp) = if black_to_move { (board.0, board.1) } else { (board.1, board.0) };
+                let moves = legal_moves(me, opp);
+                if moves == 0 {
+                    if passed {
+                        break;
+                    }
+                    passed = true;
+                    hash = zobrist_pass(hash);
+                } else {
+                    passed = false;
+                    let mut m = moves;
+                    for _ in 0..rng.next() % moves.count_ones() {
+                        m &= m - 1;
+                    }
+                    let mov = m & m.wrapping_neg();
+                    let flips = flip_disks(me, opp, mov);
+                    hash = zobrist_play(hash, black_to_move, mov, flips);
+                    let (me, opp) = (me | mov | flips, opp ^ flips);
+                    board = if black_to_move { Board(me, opp) } else { Board(opp, me) };
+                }
+                black_to_move = !black_to_move;
+                assert_eq!(hash, zobrist(&board, black_to_move));
+            }
+        }
+    }
+
+    // Where each symmetry sends cell (r, c), straight from its definition.
+    fn map_cell(symmetry: Symmetry, r: usize, c: usize) -> (usize, usize) {
+        match symmetry {
+            Symmetry::Identity => (r, c),
+            Symmetry::Rotate90 => (c, 7 - r),
+            Symmetry::Rotate180 => (7 - r, 7 - c),
+            Symmetry::Rotate270 => (7 - c, r),
+            Symmetry::FlipVertical => (7 - r, c),
+            Symmetry::FlipHorizontal => (r, 7 - c),
+            Symmetry::FlipDiagonal => (c, r),
+            Symmetry::FlipAntiDiagonal => (7 - c, 7 - r),
+        }
+    }
+
+    #[test]
+    fn symmetries_map_every_cell_as_defined() {
+        for symmetry in Symmetry::ALL {
+            for r in 0..8 {
+                for c in 0..8 {
+                    let (tr, tc) = map_cell(symmetry, r, c);
+                    assert_eq!(
+                        symmetry.apply(position_to_mask(r, c)),
+                        position_to_mask(tr, tc),
+                        "{symmetry:?} on ({r}, {c})"
+                    );
+                }
+            }
+            let mask = 0x0123_4567_89AB_CDEF;
+            assert_eq!(symmetry.inverse().apply(symmetry.apply(mask)), mask);
+        }
+    }
+
+    #[test]
+    fn symmetric_positions_share_a_canonical_form() {
+        let mut rng = Xor128::from_seed(99);
+        let mut board = Board::new();
+        for _ in 0..20 {
+            let moves = legal_moves(board.0, board.1);
+            if moves == 0 {
+                break;
+            }
+            let mut m = moves;
+            for _ in 0..rng.next() % moves.count_ones() {
+                m &= m - 1;
+            }
+            board = board.flip(m & m.wrapping_neg()).switch();
+
+            let (canonical, symmetry) = board.canonical();
+            let back = canonical.transform(symmetry.inverse());
+            assert_eq!((back.0, back.1), (board.0, board.1));
+            for s in Symmetry::ALL {
+                let image = board.transform(s);
+                // Move generation commutes with the symmetries.
+                assert_eq!(legal_moves(image.0, image.1), s.apply(legal_moves(board.0, board.1)));
+                let (c, _) = image.canonical();
+                assert_eq!((c.0, c.1), (canonical.0, canonical.1));
+            }
+        }
+    }
+
+    #[test]
+    fn zobrist_tells_sides_and_colours_apart() {
+        let b = Board::new();
+        assert_ne!(zobrist(&b, true), zobrist(&b, false));
+        assert_eq!(zobrist(&b, true) ^ zobrist(&b, false), ZOBRIST_WHITE_TO_MOVE);
+        // The colour-swapped opening is a different position.
+        assert_ne!(zobrist(&b, true), zobrist(&b.switch(), true));
+        assert_eq!(zobrist(&Board(0, 0), true), 0);
+    }
 }
@@ -0,0 +1,415 @@
+//! Opening book: statistics of game outcomes for positions near the start,
+//! grown from AB5 self-play or from imported games, and consulted by
+//! `player::book::BookPlayer` before it searches.
+//!
+//! Positions are stored in canonical form (see `Board::canonical`) from the
+//! point of view of the side to move, so the 8 symmetric images of a position,
+//! and the same position reached with colours swapped, share one entry. An
+//! entry is a visit count and the sum of the final disk differences (for the
+//! side to move) of the games that reached it; its score is their mean.
+//!
+//! The on-disk format is plain text, one position per line:
+//! `<side to move's disks> <opponent's disks> <visits> <score sum>`, the masks
+//! as 16 hex digits. Lines starting with `#` and blank lines are ignored, and
+//! lines for the same canonical position (e.g. from concatenated books) add
+//! up.
+
+use crate::reversi::bitboard::{legal_moves, Board, Mask};
+use crate::reversi::hash::FxBuildHasher;
+use crate::reversi::player::alphabeta5::AlphaBeta5Player;
+use crate::reversi::player::Player;
+use crate::reversi::rand::Xor128;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// Outcome statistics of one book position.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BookEntry {
+    pub visits: u32,
+    /// Sum over the visits of the final disk difference for the side to move.
+    pub score_sum: i64,
+}
+
+impl BookEntry {
+    /// Mean final disk difference for the side to move.
+    pub fn score(&self) -> i32 {
+        if self.visits == 0 {
+            0
+        } else {
+            (self.score_sum / self.visits as i64) as i32
+        }
+    }
+}
+
+/// A legal move of a position whose resulting position is in the book.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookMove {
+    pub mov: Mask,
+    /// Mean final disk difference for the player making the move.
+    pub score: i32,
+    pub visits: u32,
+}
+
+/// Error reading a book, or adding a game that is not a legal one.
+#[derive(Debug)]
+pub enum BookError {
+    Io(io::Error),
+    /// Line `line` (1-based) is not a valid entry.
+    Parse {
+        line: usize,
+        reason: String,
+    },
+    /// Move `ply` (0-based) of an added game is not legal.
+    IllegalMove {
+        ply: usize,
+    },
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::Io(e) => write!(f, "book i/o error: {e}"),
+            BookError::Parse { line, reason } => write!(f, "book line {line}: {reason}"),
+            BookError::IllegalMove { ply } => write!(f, "illegal move at ply {ply}"),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+impl From<io::Error> for BookError {
+    fn from(e: io::Error) -> Self {
+        BookError::Io(e)
+    }
+}
+
+/// Canonical book positions and their statistics.
+#[derive(Clone, Default)]
+pub struct Book {
+    entries: HashMap<(Mask, Mask), BookEntry, FxBuildHasher>,
+}
+
+impl Book {
+    pub fn new() -> Book {
+        Book::default()
+    }
+
+    /// Number of positions.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry of `board` (`.0` to move), if it is in the book.
+    pub fn get(&self, board: &Board) -> Option<BookEntry> {
+        let (canonical, _) = board.canonical();
+        self.entries.get(&(canonical.0, canonical.1)).copied()
+    }
+
+    /// Counts a visit of `board` (`.0` to move) in a game that ended `score`
+    /// disks up for the side to move.
+    pub fn record(&mut self, board: &Board, score: i32) {
+        let (canonical, _) = board.canonical();
+        let entry = self.entries.entry((canonical.0, canonical.1)).or_default();
+        entry.visits += 1;
+        entry.score_sum += score as i64;
+    }
+
+    /// The legal moves of `board` (`.0` to move) that lead to a book position
+    /// visited at least `min_visits` times, best score first.
+    pub fn moves(&self, board: &Board, min_visits: u32) -> Vec<BookMove> {
+        let mut moves = Vec::new();
+        let mut m = legal_moves(board.0, board.1);
+        while m != 0 {
+            let mov = m & m.wrapping_neg();
+            m &= m - 1;
+            // The child is stored from the opponent's point of view.
+            if let Some(entry) = self.get(&board.flip(mov).switch()) {
+                if entry.visits >= min_visits {
+                    moves.push(BookMove { mov, score: -entry.score(), visits: entry.visits });
+                }
+            }
+        }
+        moves.sort_by_key(|m| (std::cmp::Reverse(m.score), std::cmp::Reverse(m.visits)));
+        moves
+    }
+
+    /// Adds the positions reached by the first `max_plies` moves of a game from
+    /// the standard start. `moves` holds one entry per turn, None for a pass;
+    /// `black_score` is black's final disk difference. Nothing is added if a
+    /// move is illegal.
+    pub fn add_game(
+        &mut self,
+        moves: &[Option<Mask>],
+        black_score: i32,
+        max_plies: usize,
+    ) -> Result<(), BookError> {
+        let mut positions = Vec::new();
+        let mut board = Board::new();
+        let mut score = black_score; // for the side to move
+        for (ply, &mov) in moves.iter().enumerate() {
+            let legal = legal_moves(board.0, board.1);
+            match mov {
+                Some(mov) if mov.count_ones() == 1 && mov & legal != 0 => {
+                    board = board.flip(mov).switch();
+                    score = -score;
+                    if ply < max_plies {
+                        positions.push((board.clone(), score));
+                    }
+                }
+                None if legal == 0 => {
+                    board = board.switch();
+                    score = -score;
+                }
+                _ => return Err(BookError::IllegalMove { ply }),
+            }
+        }
+        for (board, score) in positions {
+            self.record(&board, score);
+        }
+        Ok(())
+    }
+
+    /// Reads a book in the text format described in the module docs.
+    pub fn read(reader: impl BufRead) -> Result<Book, BookError> {
+        let mut book = Book::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse_error =
+                |reason: &str| BookError::Parse { line: i + 1, reason: reason.into() };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [me, opp, visits, score_sum] = fields[..] else {
+                return Err(parse_error("expected 4 fields"));
+            };
+            let me = Mask::from_str_radix(me, 16).map_err(|_| parse_error("bad disk mask"))?;
+            let opp = Mask::from_str_radix(opp, 16).map_err(|_| parse_error("bad disk mask"))?;
+            if me & opp != 0 {
+                return Err(parse_error("overlapping disks"));
+            }
+            let visits: u32 = visits.parse().map_err(|_| parse_error("bad visit count"))?;
+            let score_sum: i64 = score_sum.parse().map_err(|_| parse_error("bad score sum"))?;
+            let (canonical, _) = Board(me, opp).canonical();
+            let entry = book.entries.entry((canonical.0, canonical.1)).or_default();
+            // Duplicates add up, which a hand-edited book could push too far.
+            entry.visits = entry
+                .visits
+                .checked_add(visits)
+                .ok_or_else(|| parse_error("visit count overflows"))?;
+            entry.score_sum = entry
+                .score_sum
+                .checked_add(score_sum)
+                .ok_or_else(|| parse_error("score sum overflows"))?;
+        }
+        Ok(book)
+    }
+
+    /// Writes the book in the text format, sorted so the output is stable.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut keys: Vec<_> = self.entries.keys().copied().collect();
+        keys.sort_unstable();
+        writeln!(
+            writer,
+            "# reversi opening book: to-move disks, opponent disks, visits, score sum"
+        )?;
+        for key in keys {
+            let e = self.entries[&key];
+            writeln!(writer, "{:016x} {:016x} {} {}", key.0, key.1, e.visits, e.score_sum)?;
+        }
+        Ok(())
+    }
+}
+
+/// Grows a book from games: AB5 self-play, or games imported with
+/// `add_game`. Only the first `max_plies` moves of each game are kept.
+pub struct BookBuilder {
+    book: Book,
+    max_plies: usize,
+}
+
+impl BookBuilder {
+    pub fn new(max_plies: usize) -> BookBuilder {
+        BookBuilder::from_book(Book::new(), max_plies)
+    }
+
+    /// Continues growing an existing book.
+    pub fn from_book(book: Book, max_plies: usize) -> BookBuilder {
+        BookBuilder { book, max_plies }
+    }
+
+    /// Adds an imported game (see `Book::add_game`).
+    pub fn add_game(&mut self, moves: &[Option<Mask>], black_score: i32) -> Result<(), BookError> {
+        self.book.add_game(moves, black_score, self.max_plies)
+    }
+
+    /// Plays `games` games of AB5 against itself at `budget` nodes per move and
+    /// adds them. The first `random_plies` moves of each game are random, so
+    /// the games spread over different openings.
+    pub fn self_play(&mut self, games: u32, seed: u32, budget: u64, random_plies: usize) {
+        let mut rng = Xor128::from_seed(seed);
+        for game in 0..games {
+            let seed = seed.wrapping_add(game.wrapping_mul(2));
+            let mut players = [
+                AlphaBeta5Player::with_budget(seed, budget),
+                AlphaBeta5Player::with_budget(seed.wrapping_add(1), budget),
+            ];
+            let mut board = Board::new();
+            let mut moves = Vec::new();
+            let mut passes = 0;
+            while passes < 2 {
+                let legal = legal_moves(board.0, board.1);
+                if legal == 0 {
+                    passes += 1;
+                    moves.push(None);
+                    board = board.switch();
+                    continue;
+                }
+                passes = 0;
+                let mov = if moves.len() < random_plies {
+                    let mut m = legal;
+                    for _ in 0..rng.next() % legal.count_ones() {
+                        m &= m - 1;
+                    }
+                    m & m.wrapping_neg()
+                } else {
+                    players[moves.len() % 2].next(&board).expect("legal move exists")
+                };
+                moves.push(Some(mov));
+                board = board.flip(mov).switch();
+            }
+            // Both trailing passes were pushed; the board is back with the
+            // side to move after the last move.
+            moves.truncate(moves.len() - 2);
+            let (me, opp) = board.count();
+            let score = me as i32 - opp as i32;
+            let black_score = if moves.len() % 2 == 0 { score } else { -score };
+            self.add_game(&moves, black_score).expect("self-play games are legal");
+        }
+    }
+
+    pub fn book(&self) -> &Book {
+        &self.book
+    }
+
+    pub fn finish(self) -> Book {
+        self.book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::bitboard::position_to_mask;
+
+    // f5 and its symmetric alternatives, as masks (row, column).
+    fn f5() -> Mask {
+        position_to_mask(4, 5)
+    }
+    fn d3() -> Mask {
+        position_to_mask(2, 3)
+    }
+
+    #[test]
+    fn symmetric_openings_share_entries() {
+        let mut book = Book::new();
+        book.add_game(&[Some(f5())], 10, 10).unwrap();
+        book.add_game(&[Some(d3())], 20, 10).unwrap();
+        assert_eq!(book.len(), 1);
+
+        // From the start every opening move leads to the same position, scored
+        // for the player who made it.
+        let moves = book.moves(&Board::new(), 1);
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|m| m.score == 15 && m.visits == 2));
+    }
+
+    #[test]
+    fn only_the_first_plies_are_kept() {
+        let mut book = Book::new();
+        let mut board = Board::new();
+        let mut moves = Vec::new();
+        for _ in 0..6 {
+            let legal = legal_moves(board.0, board.1);
+            let mov = legal & legal.wrapping_neg();
+            moves.push(Some(mov));
+            board = board.flip(mov).switch();
+        }
+        book.add_game(&moves, 4, 3).unwrap();
+        assert_eq!(book.len(), 3);
+        // After three moves white is to move, and black won by 4.
+        let mut board = Board::new();
+        for mov in &moves[..3] {
+            board = board.flip(mov.unwrap()).switch();
+        }
+        assert_eq!(book.get(&board), Some(BookEntry { visits: 1, score_sum: -4 }));
+    }
+
+    #[test]
+    fn illegal_games_are_rejected_whole() {
+        let mut book = Book::new();
+        let err = book.add_game(&[Some(f5()), Some(f5())], 0, 10).unwrap_err();
+        assert!(matches!(err, BookError::IllegalMove { ply: 1 }));
+        assert!(matches!(book.add_game(&[None], 0, 10), Err(BookError::IllegalMove { ply: 0 })));
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn text_format_round_trips() {
+        let mut builder = BookBuilder::new(8);
+        builder.self_play(3, 7, 2_000, 4);
+        let book = builder.finish();
+        assert!(book.len() >= 8);
+
+        let mut text = Vec::new();
+        book.write(&mut text).unwrap();
+        let read = Book::read(&text[..]).unwrap();
+        assert_eq!(read.len(), book.len());
+        for (key, entry) in &book.entries {
+            assert_eq!(read.entries.get(key), Some(entry));
+        }
+    }
+
+    #[test]
+    fn duplicate_positions_add_up() {
+        // The same position after f5 and, mirrored, after d3.
+        let f5 = Board::new().flip(f5()).switch();
+        let d3 = Board::new().flip(d3()).switch();
+        let text = format!("{:016x} {:016x} 2 10\n{:016x} {:016x} 3 -5\n", f5.0, f5.1, d3.0, d3.1);
+        let book = Book::read(text.as_bytes()).unwrap();
+        assert_eq!(book.len(), 1);
+        assert_eq!(book.get(&f5), Some(BookEntry { visits: 5, score_sum: 5 }));
+    }
+
+    #[test]
+    fn duplicates_that_overflow_are_reported() {
+        let f5 = Board::new().flip(f5()).switch();
+        let line = |visits: u32, score_sum: i64| {
+            format!("{:016x} {:016x} {visits} {score_sum}\n", f5.0, f5.1)
+        };
+        let text = line(u32::MAX - 1, 0) + &line(2, 0);
+        assert!(matches!(Book::read(text.as_bytes()), Err(BookError::Parse { line: 2, .. })));
+        let text = line(1, i64::MAX) + &line(1, 1);
+        assert!(matches!(Book::read(text.as_bytes()), Err(BookError::Parse { line: 2, .. })));
+        let text = line(u32::MAX - 1, 0) + &line(1, 0);
+        let book = Book::read(text.as_bytes()).unwrap();
+        assert_eq!(book.get(&f5), Some(BookEntry { visits: u32::MAX, score_sum: 0 }));
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_with_their_number() {
+        let text = "# comment\n\n0000000810000000 0000001008000000 1 2\nnot a line\n";
+        match Book::read(text.as_bytes()) {
+            Err(BookError::Parse { line: 4, .. }) => {}
+            other => panic!("unexpected {:?}", other.map(|b| b.len())),
+        }
+        let text = "0000000000000001 0000000000000001 1 2\n";
+        assert!(matches!(Book::read(text.as_bytes()), Err(BookError::Parse { line: 1, .. })));
+    }
+}
@@ -0,0 +1,307 @@
+//! GGF (Generic Game Format), the game format of GGS and most Othello
+//! software, read into and written from `GameRecord`s. A game looks like
+//!
+//! ```text
+//! (;GM[Othello]PB[alice]PW[bob]RE[+4.000]TY[8]
+//! BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]
+//! B[f5/0.50/1.20]W[d6//0.80]B[pa]...;)
+//! ```
+//!
+//! `BO` is the start position (`*` black, `O` white, then the side to move)
+//! and each `B`/`W` a move as `square/evaluation/seconds`, the last two
+//! optional; `pa` is a pass. `RE` is the disk difference for black. Only 8x8
+//! Othello is read; tags other than these are skipped, and a result's
+//! `:r`/`:t` suffix (resignation, timeout) is dropped.
+
+use crate::reversi::gm::Turn;
+use crate::reversi::notation::{ParseError, Position};
+use crate::reversi::record::{GameRecord, MoveRecord};
+use crate::reversi::transcript::{parse_square, square_name, TranscriptError};
+use std::fmt;
+
+/// Error reading a GGF game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GgfError {
+    /// No `(;` starting a game.
+    NotAGame,
+    /// The text ends inside a tag or before `;)`.
+    Unterminated,
+    /// The game (`GM`) or board type (`TY`) is not 8x8 Othello.
+    Unsupported(String),
+    MissingBoard,
+    Board(ParseError),
+    /// The result is not a number.
+    Result(String),
+    /// Move `ply` (1-based) is not `square/evaluation/seconds`.
+    Move {
+        ply: usize,
+        text: String,
+    },
+    /// Move `ply` is given to the side not to move.
+    WrongColor {
+        ply: usize,
+    },
+    /// The moves do not replay from the start position.
+    Replay(TranscriptError),
+}
+
+impl fmt::Display for GgfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GgfError::NotAGame => write!(f, "no game found (expected \"(;\")"),
+            GgfError::Unterminated => write!(f, "game is not terminated"),
+            GgfError::Unsupported(kind) => write!(f, "unsupported game type {kind:?}"),
+            GgfError::MissingBoard => write!(f, "missing start position (BO)"),
+            GgfError::Board(err) => write!(f, "start position: {err}"),
+            GgfError::Result(text) => write!(f, "invalid result {text:?}"),
+            GgfError::Move { ply, text } => write!(f, "ply {ply}: invalid move {text:?}"),
+            GgfError::WrongColor { ply } => write!(f, "ply {ply}: move by the side not to move"),
+            GgfError::Replay(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for GgfError {}
+
+/// Reads the first game of `text`.
+pub fn parse(text: &str) -> Result<GameRecord, GgfError> {
+    let start = text.find("(;").ok_or(GgfError::NotAGame)?;
+    parse_game(&text[start + 2..]).map(|(record, _)| record)
+}
+
+/// Reads every game of `text` (a GGF file holds one per line), each on its
+/// own so one broken game does not hide the others.
+pub fn parse_all(text: &str) -> Vec<Result<GameRecord, GgfError>> {
+    let mut games = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("(;") {
+        rest = &rest[start + 2..];
+        match parse_game(rest) {
+            Ok((record, after)) => {
+                games.push(Ok(record));
+                rest = after;
+            }
+            Err(err) => {
+                games.push(Err(err));
+                // Skip to the end of the broken game, if it has one.
+                rest = rest.find(";)").map_or("", |end| &rest[end + 2..]);
+            }
+        }
+    }
+    games
+}
+
+// Reads the tags of a game up to `;)`, returning the record and the text
+// after it.
+fn parse_game(mut text: &str) -> Result<(GameRecord, &str), GgfError> {
+    let mut record = GameRecord::new("", "", Position::new());
+    let mut board = None;
+    let mut moves = Vec::new();
+    loop {
+        text = text.trim_start();
+        if let Some(rest) = text.strip_prefix(";)") {
+            text = rest;
+            break;
+        }
+        let open = text.find('[').ok_or(GgfError::Unterminated)?;
+        let close = open + text[open..].find(']').ok_or(GgfError::Unterminated)?;
+        let (tag, value) = (text[..open].trim(), &text[open + 1..close]);
+        text = &text[close + 1..];
+        match tag {
+            "GM" if !value.eq_ignore_ascii_case("othello") => {
+                return Err(GgfError::Unsupported(value.to_string()));
+            }
+            "TY" if value.trim().trim_end_matches(|c: char| c.is_ascii_alphabetic()) != "8" => {
+                return Err(GgfError::Unsupported(value.to_string()));
+            }
+            "PB" => record.black = value.to_string(),
+            "PW" => record.white = value.to_string(),
+            "RE" => record.result = parse_result(value)?,
+            "BO" => board = Some(parse_board(value)?),
+            "B" | "W" => moves.push((tag == "B", value)),
+            _ => {}
+        }
+    }
+
+    record.start = board.ok_or(GgfError::MissingBoard)?;
+    let mut turn = record.start.turn;
+    for (i, (black, value)) in moves.into_iter().enumerate() {
+        let ply = i + 1;
+        if black != (turn == Turn::Black) {
+            return Err(GgfError::WrongColor { ply });
+        }
+        let mov = parse_move(value).ok_or(GgfError::Move { ply, text: value.to_string() })?;
+        record.moves.push(mov);
+        turn = turn.switch();
+    }
+    record.replay().map_err(GgfError::Replay)?;
+    Ok((record, text))
+}
+
+// `8 <64 cells> <side to move>`, black as `*`.
+fn parse_board(value: &str) -> Result<Position, GgfError> {
+    let value = value.trim_start();
+    let cells = value.strip_prefix('8').filter(|rest| rest.starts_with(char::is_whitespace));
+    let cells = cells.ok_or_else(|| GgfError::Unsupported(value.to_string()))?;
+    cells.replace('*', "X").parse().map_err(GgfError::Board)
+}
+
+// `+4.000`, `-64.000:r` or `?` (unknown).
+fn parse_result(value: &str) -> Result<Option<i32>, GgfError> {
+    let score = value.split(':').next().unwrap().trim();
+    if score == "?" || score.is_empty() {
+        return Ok(None);
+    }
+    let score: f64 = score.parse().map_err(|_| GgfError::Result(value.to_string()))?;
+    Ok(Some(score.round() as i32))
+}
+
+// `f5`, `f5/0.50`, `f5/0.50/1.20`, `f5//1.20` or `pa`.
+fn parse_move(value: &str) -> Option<MoveRecord> {
+    let mut fields = value.split('/').map(str::trim);
+    let square = fields.next()?;
+    let mov = if square.eq_ignore_ascii_case("pa") || square.eq_ignore_ascii_case("pass") {
+        None
+    } else {
+        Some(parse_square(square)?)
+    };
+    let eval = match fields.next() {
+        None | Some("") => None,
+        Some(eval) => Some(eval.parse().ok()?),
+    };
+    let time = match fields.next() {
+        None | Some("") => None,
+        Some(time) => Some(parse_seconds(time)?),
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(MoveRecord { mov, eval, time })
+}
+
+// Seconds, possibly as `minutes:seconds` or `hours:minutes:seconds`.
+fn parse_seconds(time: &str) -> Option<f64> {
+    time.split(':').try_fold(0.0, |total, part| Some(total * 60.0 + part.parse::<f64>().ok()?))
+}
+
+/// Writes `record` as a GGF game on one line.
+pub fn write(record: &GameRecord) -> String {
+    let mut text = format!("(;GM[Othello]PC[reversi]PB[{}]PW[{}]", record.black, record.white);
+    if let Some(result) = record.result {
+        text += &format!("RE[{:+.3}]", result as f64);
+    }
+    text += "TY[8]BO[8";
+    let board = record.start.board.to_string().replace('X', "*");
+    for row in board.as_bytes().chunks(8) {
+        text += " ";
+        text += std::str::from_utf8(row).unwrap();
+    }
+    let mut turn = record.start.turn;
+    text += if turn == Turn::Black { " *]" } else { " O]" };
+
+    for mov in &record.moves {
+        text += if turn == Turn::Black { "B[" } else { "W[" };
+        text += &mov.mov.map_or("pa".to_string(), square_name);
+        match (mov.eval, mov.time) {
+            (None, None) => {}
+            (eval, time) => {
+                text += &format!("/{}", eval.map_or(String::new(), |e| format!("{e:.2}")));
+                if let Some(time) = time {
+                    text += &format!("/{time:.2}");
+                }
+            }
+        }
+        text += "]";
+        turn = turn.switch();
+    }
+    text + ";)"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::gm::GameManager;
+    use crate::reversi::player::random::RandomPlayer;
+    use crate::reversi::transcript;
+
+    const GAME: &str = "(;GM[Othello]PC[GGS/os]DT[2003.12.15_13:24:03.MST]PB[alice]PW[bob]\
+        RB[2197.01]RW[2199.72]TI[15:00//02:00]TY[8]RE[-14.000:r]\
+        BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]\
+        B[f5//0.01]W[D6/-1.50/1:02]B[c3/0.25]W[d3]B[c4/+3.00/2.5];)";
+
+    #[test]
+    fn reads_a_game() {
+        let record = parse(GAME).unwrap();
+        assert_eq!((record.black.as_str(), record.white.as_str()), ("alice", "bob"));
+        assert_eq!(record.result, Some(-14));
+        assert_eq!(record.start, Position::new());
+        let moves: Vec<_> = record.moves.iter().map(|m| m.mov).collect();
+        assert_eq!(moves, transcript::parse("f5d6c3d3c4").unwrap().moves);
+        let evals: Vec<_> = record.moves.iter().map(|m| m.eval).collect();
+        assert_eq!(evals, [None, Some(-1.5), Some(0.25), None, Some(3.0)]);
+        let times: Vec<_> = record.moves.iter().map(|m| m.time).collect();
+        assert_eq!(times, [Some(0.01), Some(62.0), None, None, Some(2.5)]);
+    }
+
+    #[test]
+    fn games_round_trip() {
+        for seed in 0..10 {
+            let mut gm = GameManager::new(
+                Box::new(RandomPlayer::new(seed)),
+                Box::new(RandomPlayer::new(seed + 100)),
+            );
+            let mut record = gm.playout().record();
+            for (i, mov) in record.moves.iter_mut().enumerate() {
+                // Values the writer's two decimals keep exactly.
+                mov.eval = (i % 3 != 0).then_some(i as f64 / 4.0 - 8.0);
+                mov.time = (i % 2 == 0).then_some(i as f64 / 2.0);
+            }
+            assert_eq!(parse(&write(&record)).unwrap(), record, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn non_standard_start() {
+        // White to move; its only move, a1, takes black's last disk.
+        let start: Position =
+            "-XO------O------------------------------------------------------ O".parse().unwrap();
+        let mut record = GameRecord::new("x", "y", start);
+        record.moves = vec![MoveRecord::new(Some(1))];
+        record.result = Some(-4);
+        assert!(record.replay().is_ok());
+        let text = write(&record);
+        assert!(text.contains("BO[8 -*O----- -O------ "), "{text}");
+        assert!(text.contains(" O]W[a1];)"), "{text}");
+        assert_eq!(parse(&text).unwrap(), record);
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let board =
+            "BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]";
+        let cases = [
+            ("no game here".to_string(), GgfError::NotAGame),
+            (format!("(;GM[Othello]{board}B[f5]"), GgfError::Unterminated),
+            (format!("(;GM[Chess]{board};)"), GgfError::Unsupported("Chess".to_string())),
+            (format!("(;TY[10]{board};)"), GgfError::Unsupported("10".to_string())),
+            ("(;GM[Othello]B[f5];)".to_string(), GgfError::MissingBoard),
+            (format!("(;RE[won]{board};)"), GgfError::Result("won".to_string())),
+            (format!("(;{board}B[f5/x];)"), GgfError::Move { ply: 1, text: "f5/x".to_string() }),
+            (format!("(;{board}B[f5]B[d6];)"), GgfError::WrongColor { ply: 2 }),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse(&text), Err(expected), "{text}");
+        }
+        let err = parse(&format!("(;{board}B[f5]W[a1];)")).unwrap_err();
+        assert_eq!(err.to_string(), "ply 2: a1 flips no disk");
+    }
+
+    #[test]
+    fn reads_every_game_of_a_file() {
+        let file = format!("{GAME}\n(;GM[Othello]B[f5];)\n{GAME}\n");
+        let games = parse_all(&file);
+        assert_eq!(games.len(), 3);
+        assert!(games[0].is_ok() && games[2].is_ok());
+        assert_eq!(games[1], Err(GgfError::MissingBoard));
+    }
+}
@@ -1,7 +1,12 @@
 use crate::reversi::asciiboard;
-use crate::reversi::bitboard::{coordinate, Board, Mask};
-use crate::reversi::player::Player;
+use crate::reversi::bitboard::{coordinate, flip_disks, Board, Mask};
+use crate::reversi::notation::Position;
+use crate::reversi::player::{Player, SearchControl, SearchInfo};
+use crate::reversi::record::{GameRecord, MoveRecord};
+use crate::reversi::transcript::{self, Transcript, TranscriptError};
 use crate::reversi::util;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Player who will take the next move.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -12,7 +17,7 @@ pub enum Turn {
 
 impl Turn {
     /// The other player's turn.
-    fn switch(self) -> Turn {
+    pub fn switch(self) -> Turn {
         match self {
             Turn::Black => Turn::White,
             Turn::White => Turn::Black,
@@ -35,8 +40,25 @@ pub enum Winner {
     Draw,
 }
 
+/// One ply of a played game.
+#[derive(Clone, Debug)]
+pub struct Ply {
+    /// The side that moved (or passed).
+    pub turn: Turn,
+    /// The move in the mover's perspective, None for a pass.
+    pub mov: Option<Mask>,
+    /// The disks the move flipped.
+    pub flips: Mask,
+    /// The board after the ply (black in `.0`).
+    pub board: Board,
+    /// The mover's last search report for this move, if its engine makes any.
+    pub search: Option<SearchInfo>,
+    /// Time the mover took; None for a move of the given opening, or where
+    /// there is no clock (the browser build).
+    pub time: Option<Duration>,
+}
+
 /// The result of a game.
-/// TODO: Add sequence of moves.
 #[derive(Clone)]
 pub struct GameResult {
     pub winner: Winner,
@@ -46,6 +68,45 @@ pub struct GameResult {
 
     // Numbers of disks (black, white).
     pub disks: (u32, u32),
+
+    // The players' names (black, white).
+    pub players: (&'static str, &'static str),
+
+    // The position the game started from.
+    pub start: Position,
+
+    // Every ply from `start`, passes included.
+    pub plies: Vec<Ply>,
+}
+
+impl GameResult {
+    /// The position the game stopped at: the final one, or where
+    /// `GameManager::set_stop_empties` cut it off.
+    pub fn position(&self) -> Position {
+        let turn = self.plies.last().map_or(self.start.turn, |ply| ply.turn.switch());
+        Position { board: self.board.clone(), turn }
+    }
+
+    /// The game as a `GameRecord`, e.g. to save as GGF: the final disk
+    /// difference of each move the engine solved becomes its evaluation (its
+    /// sign only for a win/loss/draw solve, see `SearchInfo::score`).
+    /// Midgame scores are in the engine's own units, not disks, so they are
+    /// left out.
+    pub fn record(&self) -> GameRecord {
+        let (black, white) = self.players;
+        let mut record = GameRecord::new(black, white, self.start.clone());
+        record.moves = self
+            .plies
+            .iter()
+            .map(|ply| MoveRecord {
+                mov: ply.mov,
+                eval: ply.search.as_ref().filter(|info| info.solved).map(|info| info.score as f64),
+                time: ply.time.map(|time| time.as_secs_f64()),
+            })
+            .collect();
+        record.result = Some(self.disks.0 as i32 - self.disks.1 as i32);
+        record
+    }
 }
 
 /// The board as seen by the player to move: routines always assume black is to
@@ -68,6 +129,16 @@ fn play_move(board: &Board, turn: Turn, mov: Option<Mask>) -> Board {
     }
 }
 
+/// Starts timing a move, if there is a clock: `Instant::now` panics in the
+/// browser build.
+fn clock() -> Option<Instant> {
+    if cfg!(target_arch = "wasm32") {
+        None
+    } else {
+        Some(Instant::now())
+    }
+}
+
 /// Decides the winner from the disk counts of a (usually finished) board.
 fn winner_of(board: &Board) -> Winner {
     let (black, white) = board.count();
@@ -78,9 +149,22 @@ fn winner_of(board: &Board) -> Winner {
     }
 }
 
-/// Builds the result of a finished game from its final board.
-fn finalize(board: &Board) -> GameResult {
-    GameResult { winner: winner_of(board), board: board.clone(), disks: board.count() }
+/// Builds the result of a finished (or stopped) game from its final board and the plies
+/// leading to it.
+fn finalize(
+    board: &Board,
+    players: (&'static str, &'static str),
+    start: Position,
+    plies: Vec<Ply>,
+) -> GameResult {
+    GameResult {
+        winner: winner_of(board),
+        board: board.clone(),
+        disks: board.count(),
+        players,
+        start,
+        plies,
+    }
 }
 
 /// A game manager that runs a match between two players,
@@ -88,11 +172,49 @@ fn finalize(board: &Board) -> GameResult {
 pub struct GameManager {
     black: Box<dyn Player>,
     white: Box<dyn Player>,
+    start: Position,
+    // Moves played for the players from `start` before they take over.
+    opening: Vec<Option<Mask>>,
+    stop_empties: Option<u32>,
 }
 
 impl GameManager {
     pub fn new(black: Box<dyn Player>, white: Box<dyn Player>) -> GameManager {
-        GameManager { black, white }
+        GameManager::with_position(black, white, Position::new())
+    }
+
+    /// A game starting at `start` instead of the opening position, e.g. a
+    /// loaded or XOT position.
+    pub fn with_position(
+        black: Box<dyn Player>,
+        white: Box<dyn Player>,
+        start: Position,
+    ) -> GameManager {
+        GameManager { black, white, start, opening: Vec::new(), stop_empties: None }
+    }
+
+    /// A game from the opening position whose first moves are `opening` (None
+    /// for a pass), played for the players. They are kept in the result's
+    /// plies, without search or time.
+    pub fn with_opening(
+        black: Box<dyn Player>,
+        white: Box<dyn Player>,
+        opening: &[Option<Mask>],
+    ) -> Result<GameManager, TranscriptError> {
+        let mut transcript = Transcript::new(&Position::new());
+        for &mov in opening {
+            transcript.push(mov)?;
+        }
+        let mut gm = GameManager::new(black, white);
+        gm.opening = transcript.moves;
+        Ok(gm)
+    }
+
+    /// Stops the game, before anyone moves, once only `empties` empty squares
+    /// are left. None (the default) plays to the end. The result then has the
+    /// board and disks where the game stopped (`GameResult::position`).
+    pub fn set_stop_empties(&mut self, empties: Option<u32>) {
+        self.stop_empties = empties;
     }
 
     /// Plays the game to the end silently and returns the result.
@@ -107,34 +229,76 @@ impl GameManager {
         self.run(Some(&reporter))
     }
 
-    // Folds board transitions from the opening until neither side can move.
-    // `reporter`, when present, is the sole sink for stdout side effects.
+    // Folds board transitions from the start until neither side can move (or
+    // the stop is reached). `reporter`, when present, is the sole sink for
+    // stdout side effects.
     fn run(&mut self, reporter: Option<&Reporter>) -> GameResult {
-        let mut board = Board::new();
-        let mut turn = Turn::Black;
+        let start = self.start.clone();
+        let mut board = start.board.clone();
+        let mut turn = start.turn;
+        let mut plies = Vec::new();
+        let mut opening = self.opening.iter();
+
+        // Keeps the last report of the search in progress for the plies. The
+        // recorder is chained onto whatever control a player already has, so
+        // the caller's stop flag and progress callback keep working, and that
+        // control is put back after the game.
+        let last: Arc<Mutex<Option<SearchInfo>>> = Arc::default();
+        let record = |control: Option<SearchControl>| {
+            let last = Arc::clone(&last);
+            let listener = move |info: &SearchInfo| *last.lock().unwrap() = Some(info.clone());
+            match control {
+                Some(control) => control.with_listener(listener),
+                None => SearchControl::with_progress(listener),
+            }
+        };
+        let (black_control, white_control) = (self.black.control(), self.white.control());
+        self.black.set_control(Some(record(black_control.clone())));
+        self.white.set_control(Some(record(white_control.clone())));
 
         while board.continues() {
+            let empties = 64 - (board.0 | board.1).count_ones();
+            if self.stop_empties.is_some_and(|stop| empties <= stop) {
+                break;
+            }
             if let Some(r) = reporter {
                 r.separator();
             }
 
             let view = perspective(&board, turn);
-            let mov = match turn {
-                Turn::Black => self.black.next(&view),
-                Turn::White => self.white.next(&view),
+            let (mov, search, time) = match opening.next() {
+                Some(&mov) => (mov, None, None),
+                None => {
+                    let clock = clock();
+                    let mov = match turn {
+                        Turn::Black => self.black.next(&view),
+                        Turn::White => self.white.next(&view),
+                    };
+                    let time = clock.map(|clock| clock.elapsed());
+                    // A report about another move (e.g. a deliberately
+                    // weakened choice) says nothing about this one.
+                    let search =
+                        last.lock().unwrap().take().filter(|info| Some(info.best_move) == mov);
+                    (mov, search, time)
+                }
             };
             if let Some(mov) = mov {
                 debug_assert!(mov.count_ones() == 1);
             }
+            let flips = mov.map_or(0, |mov| flip_disks(view.0, view.1, mov));
 
             board = play_move(&board, turn, mov);
             if let Some(r) = reporter {
-                r.ply(turn, mov, &board);
+                r.ply(turn, mov, search.as_ref(), &board);
             }
+            plies.push(Ply { turn, mov, flips, board: board.clone(), search, time });
             turn = turn.switch();
         }
+        self.black.set_control(black_control);
+        self.white.set_control(white_control);
 
-        let result = finalize(&board);
+        let players = (self.black.name(), self.white.name());
+        let result = finalize(&board, players, start, plies);
         if let Some(r) = reporter {
             r.result(&result);
         }
@@ -142,7 +306,6 @@ impl GameManager {
     }
 }
 
-
 /// Verbose reporter of game progress and result to stdout.
 struct Reporter {
     black: &'static str,
@@ -161,8 +324,9 @@ impl Reporter {
         println!("==================================================");
     }
 
-    // Reports the move (or pass) `turn` just made, followed by the score line.
-    fn ply(&self, turn: Turn, mov: Option<Mask>, board: &Board) {
+    // Reports the move (or pass) `turn` just made with the line its engine
+    // expects, followed by the score line.
+    fn ply(&self, turn: Turn, mov: Option<Mask>, search: Option<&SearchInfo>, board: &Board) {
         let color = match turn {
             Turn::Black => "black",
             Turn::White => "white",
@@ -179,6 +343,9 @@ impl Reporter {
             }
             None => println!("{} ({}) passed.", color, self.name(turn)),
         }
+        if let Some(info) = search.filter(|info| !info.pv.is_empty()) {
+            println!("expected line: {}", transcript::write_line(&info.pv));
+        }
 
         let (black, white) = board.count();
         println!(
@@ -200,7 +367,9 @@ impl Reporter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::reversi::bitboard::position_to_mask;
     use crate::reversi::player::random::RandomPlayer;
+    use crate::reversi::transcript;
 
     #[test]
     fn turn_switch_toggles() {
@@ -243,17 +412,23 @@ mod tests {
 
     #[test]
     fn winner_of_reads_majority() {
-        // Two black disks, one white disk.
-        assert_eq!(winner_of(&Board(0b11, 0b100)), Winner::Black);
-        assert_eq!(winner_of(&Board(0b100, 0b11)), Winner::White);
-        assert_eq!(winner_of(&Board(0b1, 0b10)), Winner::Draw);
+        let board = |cells: &str| cells.parse::<Board>().unwrap();
+        let black = board("XXO-------------------------------------------------------------");
+        let white = board("OOX-------------------------------------------------------------");
+        let draw = board("XO--------------------------------------------------------------");
+        assert_eq!(winner_of(&black), Winner::Black);
+        assert_eq!(winner_of(&white), Winner::White);
+        assert_eq!(winner_of(&draw), Winner::Draw);
     }
 
     #[test]
     fn finalize_reports_counts_and_winner() {
-        let result = finalize(&Board(0b111, 0b1000));
+        let board: Board =
+            "XXXO------------------------------------------------------------".parse().unwrap();
+        let result = finalize(&board, ("a", "b"), Position::new(), Vec::new());
         assert_eq!(result.disks, (3, 1));
         assert_eq!(result.winner, Winner::Black);
+        assert_eq!(result.record().result, Some(2));
     }
 
     #[test]
@@ -276,5 +451,146 @@ mod tests {
         assert_eq!(first.board.count(), first.disks);
         assert!(black + white <= 64);
         assert!(!first.board.continues(), "playout must stop at a terminal board");
+
+        // The plies replay from the start to the final board.
+        let transcript = first.record().replay().unwrap();
+        assert_eq!(transcript.position().board, first.board);
+        for (ply, position) in first.plies.iter().zip(&transcript.positions) {
+            assert_eq!(ply.turn, position.turn);
+            let view = position.to_move();
+            let flips = ply.mov.map_or(0, |mov| view.flip(mov).0 & view.1);
+            assert_eq!(ply.flips, flips);
+        }
+        let boards: Vec<_> = transcript.positions[1..].iter().map(|p| p.board.clone()).collect();
+        assert_eq!(first.plies.iter().map(|p| p.board.clone()).collect::<Vec<_>>(), boards);
+        assert_eq!(first.players, ("Random", "Random"));
+    }
+
+    #[test]
+    fn starts_from_a_position_or_an_opening() {
+        let random = || Box::new(RandomPlayer::new(5)) as Box<dyn Player>;
+        let start: Position =
+            "---------------------------OX------XXX-------------------------- O".parse().unwrap();
+        let result = GameManager::with_position(random(), random(), start.clone()).playout();
+        assert_eq!(result.start, start);
+        assert_eq!(result.plies[0].turn, Turn::White);
+        let mut replay = Transcript::new(&start);
+        for ply in &result.plies {
+            replay.push(ply.mov).unwrap();
+        }
+        assert_eq!(replay.position().board, result.board);
+
+        let opening = transcript::parse("f5d6c3").unwrap().moves;
+        let mut gm = GameManager::with_opening(random(), random(), &opening).unwrap();
+        let result = gm.playout();
+        let played: Vec<_> = result.plies.iter().map(|ply| ply.mov).collect();
+        assert_eq!(played[..3], opening);
+        assert!(result.plies[..3].iter().all(|ply| ply.search.is_none() && ply.time.is_none()));
+        assert!(result.plies[3..].iter().all(|ply| ply.time.is_some()));
+        assert!(!result.board.continues());
+
+        let illegal = [Some(position_to_mask(4, 5)), Some(1)];
+        let err = GameManager::with_opening(random(), random(), &illegal).err().unwrap();
+        assert_eq!(err.ply, 2);
+    }
+
+    #[test]
+    fn stops_at_empties() {
+        let mut gm =
+            GameManager::new(Box::new(RandomPlayer::new(1)), Box::new(RandomPlayer::new(2)));
+        gm.set_stop_empties(Some(20));
+        let result = gm.playout();
+        let position = result.position();
+        assert_eq!(64 - (position.board.0 | position.board.1).count_ones(), 20);
+        assert_eq!(position.board, result.board);
+        let last = result.plies.last().unwrap();
+        assert_eq!(position.turn, last.turn.switch());
+        assert!(position.board.continues());
+    }
+
+    #[test]
+    fn plies_keep_engine_reports() {
+        use crate::reversi::player::alphabeta5::AlphaBeta5Player;
+        let mut gm =
+            GameManager::new(Box::new(AlphaBeta5Player::new(1)), Box::new(RandomPlayer::new(3)));
+        let result = gm.playout();
+        let mut searched = 0;
+        for ply in &result.plies {
+            match (ply.turn, &ply.search) {
+                (Turn::Black, Some(info)) => {
+                    assert_eq!(Some(info.best_move), ply.mov);
+                    // A solve can come straight out of the table, without nodes.
+                    assert!(info.depth > 0 && (info.nodes > 0 || info.solved));
+                    searched += 1;
+                }
+                (Turn::Black, None) => {}
+                (Turn::White, search) => {
+                    assert!(search.is_none(), "the random player reports nothing")
+                }
+            }
+        }
+        assert!(searched > 0, "no search recorded for the engine");
+
+        let record = result.record();
+        assert!(record.moves.iter().any(|mov| mov.eval.is_some()), "no solved move recorded");
+        for (mov, ply) in record.moves.iter().zip(&result.plies) {
+            let solved = ply.search.as_ref().filter(|info| info.solved);
+            assert_eq!(mov.eval, solved.map(|info| info.score as f64));
+            assert_eq!(mov.time, ply.time.map(|time| time.as_secs_f64()));
+            assert!(ply.time.is_some());
+        }
+    }
+
+    #[test]
+    fn callers_control_survives_a_game() {
+        use crate::reversi::player::alphabeta5::AlphaBeta5Player;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Forwards to the engine and remembers the control it was left with.
+        struct Watched(AlphaBeta5Player, Arc<Mutex<Option<SearchControl>>>);
+        impl Player for Watched {
+            fn next(&mut self, board: &Board) -> Option<Mask> {
+                self.0.next(board)
+            }
+            fn name(&self) -> &'static str {
+                self.0.name()
+            }
+            fn set_control(&mut self, control: Option<SearchControl>) {
+                *self.1.lock().unwrap() = control.clone();
+                self.0.set_control(control)
+            }
+            fn control(&self) -> Option<SearchControl> {
+                self.0.control()
+            }
+        }
+
+        let reports = Arc::new(AtomicUsize::new(0));
+        let control = {
+            let reports = Arc::clone(&reports);
+            SearchControl::with_progress(move |_| {
+                reports.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        let left: Arc<Mutex<Option<SearchControl>>> = Arc::default();
+        let mut engine = Watched(AlphaBeta5Player::new(1), Arc::clone(&left));
+        engine.set_control(Some(control.clone()));
+        let mut gm = GameManager::new(Box::new(engine), Box::new(RandomPlayer::new(3)));
+        let result = gm.playout();
+
+        assert!(reports.load(Ordering::Relaxed) > 0, "the caller's callback was cut off");
+        assert!(result.plies.iter().any(|ply| ply.search.is_some()), "nothing recorded");
+        let left = left.lock().unwrap().take().expect("the caller's control was dropped");
+        let before = reports.load(Ordering::Relaxed);
+        left.report(&SearchInfo {
+            depth: 1,
+            score: 0,
+            best_move: 1,
+            nodes: 1,
+            pv: Vec::new(),
+            solved: false,
+        });
+        assert_eq!(reports.load(Ordering::Relaxed), before + 1);
+        control.stop();
+        assert!(left.is_stopped(), "the caller's stop flag was replaced");
     }
 }
@@ -0,0 +1,230 @@
+//! Text notation of positions: the common 64-character board string, one
+//! character per cell from a1 to h8 row by row (`X` black, `O` white, `-`
+//! empty), followed by the side to move, `X` or `O`. The opening position is
+//!
+//! ```text
+//! ---------------------------OX------XO--------------------------- X
+//! ```
+//!
+//! Whitespace is ignored when parsing, so a board may also be written as eight
+//! lines of eight cells, and `x`/`o` are accepted for `X`/`O`.
+//!
+//! `Board` parses and prints the 64 cells alone (`.0` black, `.1` white);
+//! `Position` adds the side to move.
+
+use crate::reversi::bitboard::{Board, Mask};
+use crate::reversi::gm::Turn;
+use crate::reversi::util;
+use std::fmt;
+use std::str::FromStr;
+
+/// A board with its side to move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// Black's disks in `.0`, white's in `.1`.
+    pub board: Board,
+    pub turn: Turn,
+}
+
+impl Position {
+    /// The opening position, black to move.
+    pub fn new() -> Position {
+        Position { board: Board::new(), turn: Turn::Black }
+    }
+
+    /// The board as seen by the side to move (in `.0`), as the players take it.
+    pub fn to_move(&self) -> Board {
+        match self.turn {
+            Turn::Black => self.board.clone(),
+            Turn::White => self.board.switch(),
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Position {
+        Position::new()
+    }
+}
+
+/// Error parsing a board or position string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The character for cell `index` (0 = a1, 63 = h8) is not `X`, `O` or `-`.
+    InvalidCell { index: usize, found: char },
+    /// The input ends after `cells` cells.
+    TooFewCells { cells: usize },
+    /// The 64 cells are not followed by the side to move.
+    MissingSideToMove,
+    /// The side to move is not `X` or `O`.
+    InvalidSideToMove(char),
+    /// Something follows the board (or the side to move for a position).
+    TrailingInput(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidCell { index, found } => {
+                let name = util::position_to_name(index / 8, index % 8);
+                write!(f, "invalid cell {found:?} at {name} (expected X, O or -)")
+            }
+            ParseError::TooFewCells { cells } => {
+                write!(f, "board has only {cells} of 64 cells")
+            }
+            ParseError::MissingSideToMove => write!(f, "missing side to move (X or O)"),
+            ParseError::InvalidSideToMove(c) => {
+                write!(f, "invalid side to move {c:?} (expected X or O)")
+            }
+            ParseError::TrailingInput(rest) => write!(f, "unexpected trailing input {rest:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Reads the 64 cells off the front of `chars` (whitespace skipped).
+fn parse_cells(chars: &mut impl Iterator<Item = char>) -> Result<Board, ParseError> {
+    let (mut black, mut white): (Mask, Mask) = (0, 0);
+    for index in 0..64 {
+        let cell = 1 << index;
+        match chars.next() {
+            Some('X' | 'x') => black |= cell,
+            Some('O' | 'o') => white |= cell,
+            Some('-') => {}
+            Some(found) => return Err(ParseError::InvalidCell { index, found }),
+            None => return Err(ParseError::TooFewCells { cells: index }),
+        }
+    }
+    Ok(Board(black, white))
+}
+
+fn check_end(chars: impl Iterator<Item = char>) -> Result<(), ParseError> {
+    let rest: String = chars.collect();
+    if rest.is_empty() {
+        Ok(())
+    } else {
+        Err(ParseError::TrailingInput(rest))
+    }
+}
+
+impl FromStr for Board {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Board, ParseError> {
+        let mut chars = s.chars().filter(|c| !c.is_whitespace());
+        let board = parse_cells(&mut chars)?;
+        check_end(chars)?;
+        Ok(board)
+    }
+}
+
+impl FromStr for Position {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Position, ParseError> {
+        let mut chars = s.chars().filter(|c| !c.is_whitespace());
+        let board = parse_cells(&mut chars)?;
+        let turn = match chars.next() {
+            Some('X' | 'x') => Turn::Black,
+            Some('O' | 'o') => Turn::White,
+            Some(c) => return Err(ParseError::InvalidSideToMove(c)),
+            None => return Err(ParseError::MissingSideToMove),
+        };
+        check_end(chars)?;
+        Ok(Position { board, turn })
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for index in 0..64 {
+            let cell = 1 << index;
+            let c = if self.0 & cell != 0 {
+                'X'
+            } else if self.1 & cell != 0 {
+                'O'
+            } else {
+                '-'
+            };
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let side = match self.turn {
+            Turn::Black => 'X',
+            Turn::White => 'O',
+        };
+        write!(f, "{} {side}", self.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPENING: &str = "---------------------------OX------XO--------------------------- X";
+
+    #[test]
+    fn opening_round_trip() {
+        let position: Position = OPENING.parse().unwrap();
+        assert_eq!(position, Position::new());
+        assert_eq!(position.to_string(), OPENING);
+    }
+
+    #[test]
+    fn board_alone_round_trip() {
+        let board: Board = OPENING[..64].parse().unwrap();
+        assert_eq!(board, Board::new());
+        assert_eq!(board.to_string(), &OPENING[..64]);
+    }
+
+    #[test]
+    fn whitespace_and_lowercase_are_accepted() {
+        let position: Position = "
+            x-------
+            --------
+            --------
+            --------
+            --------
+            --------
+            --------
+            -------o
+            o"
+        .parse()
+        .unwrap();
+        assert_eq!(position.board, Board(1, 1 << 63));
+        assert_eq!(position.turn, Turn::White);
+        assert_eq!(position.to_move(), Board(1 << 63, 1));
+    }
+
+    #[test]
+    fn malformed_input_is_reported() {
+        let cells = &OPENING[..64];
+        let cases = [
+            (cells[..40].to_string(), ParseError::TooFewCells { cells: 40 }),
+            (
+                format!("{}*{} X", &cells[..10], &cells[11..]),
+                ParseError::InvalidCell { index: 10, found: '*' },
+            ),
+            (cells.to_string(), ParseError::MissingSideToMove),
+            (format!("{cells} B"), ParseError::InvalidSideToMove('B')),
+            (format!("{cells} X O"), ParseError::TrailingInput("O".to_string())),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<Position>(), Err(expected), "{input:?}");
+        }
+        // A board string takes no side to move.
+        assert_eq!(OPENING.parse::<Board>(), Err(ParseError::TrailingInput("X".to_string())));
+    }
+
+    #[test]
+    fn errors_name_the_cell() {
+        let err = format!("{}?", &OPENING[..20]).parse::<Board>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid cell '?' at E3 (expected X, O or -)");
+    }
+}
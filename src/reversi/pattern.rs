@@ -0,0 +1,297 @@
+//! Pattern-based evaluation in the style of strong Othello programs.
+//!
+//! The board is read through a set of fixed cell patterns: the edge plus both
+//! X squares, the 3x3 and 2x5 corner blocks, and the diagonals of length 4 to
+//! 8. Each pattern is laid over the board under all 8 symmetries, and the state
+//! of its cells (empty, side to move, opponent) forms a ternary number that
+//! indexes a table of weights. The evaluation is the sum of the weights looked
+//! up over all pattern instances, from the table set of the current game phase.
+//! Since every symmetric image of a pattern shares one table, symmetric
+//! positions always evaluate the same.
+//!
+//! Some patterns are their own image under a symmetry (the edge under a
+//! mirror, the 3x3 corner and the diagonals under a reflection), which would
+//! lay them over the same cells twice. Such duplicates are dropped, and the
+//! orders the remaining instance can read its cells in share one entry, so
+//! every group of cells counts once.
+//!
+//! Weights are predicted final disk differences for the side to move, in
+//! hundredths of a disk. They start at zero and are fitted to labelled
+//! positions with `train`, then saved and loaded with `write` and `read`.
+
+use crate::reversi::bitboard::{position_to_mask, Board, Symmetry};
+use std::io::{self, Read, Write};
+use std::sync::{Arc, OnceLock};
+
+/// Number of game phases with their own tables, by move number.
+pub const PHASES: usize = 6;
+
+// Base cells (row, column) of each pattern kind, in index order.
+const KINDS: [&[(usize, usize)]; 8] = [
+    // Edge plus both X squares.
+    &[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5), (0, 6), (0, 7), (1, 1), (1, 6)],
+    // 3x3 corner.
+    &[(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)],
+    // 2x5 corner.
+    &[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (1, 0), (1, 1), (1, 2), (1, 3), (1, 4)],
+    // Diagonals.
+    &[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6), (7, 7)],
+    &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7)],
+    &[(0, 2), (1, 3), (2, 4), (3, 5), (4, 6), (5, 7)],
+    &[(0, 3), (1, 4), (2, 5), (3, 6), (4, 7)],
+    &[(0, 4), (1, 5), (2, 6), (3, 7)],
+];
+
+/// One pattern laid over the board: its cells in index order, and where its
+/// kind's table starts within a phase.
+struct Instance {
+    cells: Vec<u8>,
+    offset: usize,
+    /// For a pattern that is its own image under some symmetries: the least
+    /// index of the same cell states read in any of those orders, by index.
+    canonical: Option<Arc<[u32]>>,
+}
+
+/// Entries in one phase's tables.
+fn phase_len() -> usize {
+    KINDS.iter().map(|cells| 3usize.pow(cells.len() as u32)).sum()
+}
+
+fn instances() -> &'static [Instance] {
+    static INSTANCES: OnceLock<Vec<Instance>> = OnceLock::new();
+    INSTANCES.get_or_init(|| {
+        let mut instances = Vec::new();
+        let mut offset = 0;
+        for cells in KINDS {
+            let images: Vec<Vec<u8>> = Symmetry::ALL
+                .iter()
+                .map(|&symmetry| {
+                    let cell = |&(r, c)| symmetry.apply(position_to_mask(r, c)).trailing_zeros();
+                    cells.iter().map(|rc| cell(rc) as u8).collect()
+                })
+                .collect();
+            // The orders the base pattern's own cells come in, as positions
+            // into its base order.
+            let base = &images[0];
+            let orders: Vec<Vec<usize>> = images
+                .iter()
+                .filter(|image| mask_of(image) == mask_of(base))
+                .map(|image| {
+                    image.iter().map(|c| base.iter().position(|b| b == c).unwrap()).collect()
+                })
+                .collect();
+            let canonical = (orders.len() > 1).then(|| canonical_indices(cells.len(), &orders));
+            let mut seen = Vec::new();
+            for cells in images {
+                if seen.contains(&mask_of(&cells)) {
+                    continue;
+                }
+                seen.push(mask_of(&cells));
+                instances.push(Instance { cells, offset, canonical: canonical.clone() });
+            }
+            offset += 3usize.pow(cells.len() as u32);
+        }
+        instances
+    })
+}
+
+fn mask_of(cells: &[u8]) -> u64 {
+    cells.iter().fold(0, |mask, &cell| mask | 1 << cell)
+}
+
+/// For each index of an `n`-cell pattern, the least index of the same cell
+/// states read in any of `orders` (each the positions, in base order, to read
+/// the cells from).
+fn canonical_indices(n: usize, orders: &[Vec<usize>]) -> Arc<[u32]> {
+    (0..3u32.pow(n as u32))
+        .map(|index| {
+            // Digits in base order, the first cell most significant.
+            let digit = |i: usize| index / 3u32.pow((n - 1 - i) as u32) % 3;
+            let read = |order: &Vec<usize>| order.iter().fold(0, |r, &i| r * 3 + digit(i));
+            orders.iter().map(read).min().unwrap()
+        })
+        .collect()
+}
+
+/// Phase of a position with `empties` empty cells: one per 10 moves played.
+#[inline]
+fn phase(empties: u32) -> usize {
+    ((60 - empties.min(60)) as usize / 10).min(PHASES - 1)
+}
+
+/// The table entries (within the position's phase) of every pattern instance.
+#[inline]
+fn indices(board: &Board) -> impl Iterator<Item = usize> + '_ {
+    instances().iter().map(move |instance| {
+        let index = instance.cells.iter().fold(0, |index, &cell| {
+            let state = (board.0 >> cell) & 1 | ((board.1 >> cell) & 1) << 1;
+            index * 3 + state as usize
+        });
+        match &instance.canonical {
+            Some(canonical) => instance.offset + canonical[index] as usize,
+            None => instance.offset + index,
+        }
+    })
+}
+
+/// The weight tables of every phase.
+#[derive(Clone)]
+pub struct PatternWeights {
+    // Phase-major: `PHASES` blocks of `phase_len()` entries.
+    tables: Vec<i16>,
+}
+
+impl Default for PatternWeights {
+    fn default() -> Self {
+        PatternWeights { tables: vec![0; PHASES * phase_len()] }
+    }
+}
+
+// Header of the weights file, followed by a little-endian u32 format version
+// and every table entry as a little-endian i16, phase by phase.
+const MAGIC: &[u8; 4] = b"RVPW";
+const VERSION: u32 = 2;
+
+impl PatternWeights {
+    /// All-zero weights.
+    pub fn new() -> PatternWeights {
+        PatternWeights::default()
+    }
+
+    /// Predicted final disk difference for the side to move (`.0`), in
+    /// hundredths of a disk.
+    #[inline]
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let table = &self.tables[phase(64 - (board.0 | board.1).count_ones()) * phase_len()..];
+        indices(board).map(|i| table[i] as i32).sum()
+    }
+
+    /// Fits the weights to `samples`, each a position and the final disk
+    /// difference for its side to move, by `epochs` passes of stochastic
+    /// gradient descent on the squared error with step `rate`. Returns the mean
+    /// squared error (in disks²) over the last pass.
+    pub fn train(&mut self, samples: &[(Board, i32)], epochs: usize, rate: f32) -> f32 {
+        let mut weights: Vec<f32> = self.tables.iter().map(|&w| w as f32 / 100.0).collect();
+        let mut mse = 0.0;
+        for _ in 0..epochs {
+            let mut total = 0.0;
+            for (board, label) in samples {
+                let base = phase(64 - (board.0 | board.1).count_ones()) * phase_len();
+                let predicted: f32 = indices(board).map(|i| weights[base + i]).sum();
+                let error = *label as f32 - predicted;
+                total += error * error;
+                for i in indices(board) {
+                    weights[base + i] += rate * error;
+                }
+            }
+            mse = total / samples.len().max(1) as f32;
+        }
+        self.tables = weights
+            .iter()
+            .map(|w| (w * 100.0).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16)
+            .collect();
+        mse
+    }
+
+    /// Reads weights written by `write`.
+    pub fn read(mut reader: impl Read) -> io::Result<PatternWeights> {
+        let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, reason.to_string());
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a pattern weights file"));
+        }
+        if header[4..] != VERSION.to_le_bytes() {
+            return Err(invalid("unsupported pattern weights version"));
+        }
+        let mut bytes = vec![0; PHASES * phase_len() * 2];
+        reader.read_exact(&mut bytes).map_err(|_| invalid("truncated pattern weights"))?;
+        if reader.read(&mut [0])? != 0 {
+            return Err(invalid("trailing data after pattern weights"));
+        }
+        let tables = bytes.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+        Ok(PatternWeights { tables })
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        let bytes: Vec<u8> = self.tables.iter().flat_map(|w| w.to_le_bytes()).collect();
+        writer.write_all(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::playout::random_samples;
+    use crate::reversi::rand::Xor128;
+
+    fn random_weights(seed: u32) -> PatternWeights {
+        let mut rng = Xor128::from_seed(seed);
+        let mut weights = PatternWeights::new();
+        for w in weights.tables.iter_mut() {
+            *w = (rng.next() % 2001) as i16 - 1000;
+        }
+        weights
+    }
+
+    #[test]
+    fn every_pattern_is_laid_under_every_symmetry() {
+        // The edge, the 3x3 corner and the diagonals are their own mirror
+        // images, the main diagonal under four symmetries, so they lie over
+        // fewer than 8 distinct cell groups.
+        let groups = [4, 4, 8, 2, 4, 4, 4, 4];
+        assert_eq!(instances().len(), groups.iter().sum::<usize>());
+        let mut masks: Vec<u64> = instances().iter().map(|i| mask_of(&i.cells)).collect();
+        masks.sort_unstable();
+        masks.dedup();
+        assert_eq!(masks.len(), instances().len(), "cells laid over twice");
+        assert_eq!(phase_len(), 59049 * 2 + 19683 + 6561 + 2187 + 729 + 243 + 81);
+        for instance in instances() {
+            let mut seen = 0u64;
+            for &cell in &instance.cells {
+                assert_eq!(seen & 1 << cell, 0, "cell {cell} repeated");
+                seen |= 1 << cell;
+            }
+        }
+        assert_eq!((phase(60), phase(51), phase(50), phase(0)), (0, 0, 1, 5));
+    }
+
+    #[test]
+    fn symmetric_positions_evaluate_the_same() {
+        let weights = random_weights(1);
+        for (board, _) in random_samples(2, 3) {
+            let value = weights.evaluate(&board);
+            for symmetry in Symmetry::ALL {
+                assert_eq!(weights.evaluate(&board.transform(symmetry)), value);
+            }
+        }
+    }
+
+    #[test]
+    fn training_reduces_the_error() {
+        let samples = random_samples(3, 200);
+        let mut weights = PatternWeights::new();
+        let first = weights.train(&samples, 1, 0.002);
+        let last = weights.train(&samples, 5, 0.002);
+        assert!(last < first * 0.8, "mse {first} -> {last}");
+        assert!(samples.iter().any(|(board, _)| weights.evaluate(board) != 0));
+    }
+
+    #[test]
+    fn weights_file_round_trips() {
+        let weights = random_weights(4);
+        let mut bytes = Vec::new();
+        weights.write(&mut bytes).unwrap();
+        let read = PatternWeights::read(&bytes[..]).unwrap();
+        assert_eq!(read.tables, weights.tables);
+
+        assert!(PatternWeights::read(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(PatternWeights::read(&trailing[..]).is_err());
+        bytes[0] = b'X';
+        assert!(PatternWeights::read(&bytes[..]).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Perft: counts the leaves of the full game tree to a fixed depth, the
+//! standard exhaustive check of a move generator (`legal_moves` and
+//! `flip_disks`).
+//!
+//! A pass is a ply of its own, and a game that ends before `depth` is one
+//! leaf where it ends. These are the conventions of the usual published
+//! Othello perft tables, which only start to depend on them at depth 9 (the
+//! shortest game has nine moves):
+//!
+//! | depth | leaves      |
+//! |-------|-------------|
+//! | 1     | 4           |
+//! | 2     | 12          |
+//! | 3     | 56          |
+//! | 4     | 244         |
+//! | 5     | 1,396       |
+//! | 6     | 8,200       |
+//! | 7     | 55,092      |
+//! | 8     | 390,216     |
+//! | 9     | 3,005,288   |
+//! | 10    | 24,571,284  |
+//! | 11    | 212,258,800 |
+//!
+//! From the command line: `cargo run --release -- perft <depth> [divide]
+//! [<position>]`, with the position in the text notation of `notation`.
+
+use crate::reversi::bitboard::*;
+
+/// Leaves of the game tree below `board` (side to move in `.0`) at `depth`
+/// plies, see the module docs.
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    perft_rec(board.0, board.1, depth, false)
+}
+
+fn perft_rec(me: Mask, opp: Mask, depth: u32, passed: bool) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut moves = legal_moves(me, opp);
+    if moves == 0 {
+        if passed {
+            // Neither side can move: the game is over.
+            return 1;
+        }
+        return perft_rec(opp, me, depth - 1, true);
+    }
+    if depth == 1 {
+        return moves.count_ones() as u64;
+    }
+    let mut leaves = 0;
+    while moves != 0 {
+        let mov = moves & moves.wrapping_neg();
+        moves ^= mov;
+        let flips = flip_disks(me, opp, mov);
+        leaves += perft_rec(opp ^ flips, me | mov | flips, depth - 1, false);
+    }
+    leaves
+}
+
+/// `perft` split by the first move: each legal move (`None` for a pass) of
+/// `board` with the leaves below it, in square order. For tracking down a
+/// miscount against another generator. A finished game has no first move to
+/// split by, so it divides into nothing, although `perft` counts it as the
+/// one leaf where the game ends.
+pub fn divide(board: &Board, depth: u32) -> Vec<(Option<Mask>, u64)> {
+    assert!(depth > 0, "divide needs at least one ply");
+    let moves = legal_moves(board.0, board.1);
+    if moves == 0 {
+        if legal_moves(board.1, board.0) == 0 {
+            return Vec::new();
+        }
+        return vec![(None, perft_rec(board.1, board.0, depth - 1, true))];
+    }
+    (0..64)
+        .map(|i| 1u64 << i)
+        .filter(|mov| moves & mov != 0)
+        .map(|mov| (Some(mov), perft(&board.flip(mov).switch(), depth - 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opening_counts() {
+        let expected = [4, 12, 56, 244, 1_396, 8_200, 55_092, 390_216];
+        for (depth, &leaves) in (1..).zip(expected.iter()) {
+            assert_eq!(perft(&Board::new(), depth), leaves, "depth {depth}");
+        }
+    }
+
+    #[test]
+    fn depth_zero_is_the_position_itself() {
+        assert_eq!(perft(&Board::new(), 0), 1);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board = Board::new().flip(1 << 19).switch();
+        let split = divide(&board, 5);
+        assert_eq!(split.len() as u32, legal_moves(board.0, board.1).count_ones());
+        assert_eq!(split.iter().map(|&(_, n)| n).sum::<u64>(), perft(&board, 5));
+    }
+
+    #[test]
+    fn pass_is_a_ply() {
+        // The mover's only disk is b1, which a1 cannot be flanked against, so
+        // it must pass; the opponent then takes it with c1 and the game is over.
+        let board: Board =
+            "OX--------------------------------------------------------------".parse().unwrap();
+        assert_eq!(legal_moves(board.0, board.1), 0);
+        assert_eq!(perft(&board, 1), 1);
+        assert_eq!(perft(&board, 2), 1);
+        assert_eq!(divide(&board, 2), vec![(None, 1)]);
+        let after = board.switch().flip(1 << 2);
+        assert!(!after.continues());
+    }
+
+    #[test]
+    fn finished_game_is_one_leaf() {
+        let board = Board(u64::MAX, 0);
+        for depth in 0..4 {
+            assert_eq!(perft(&board, depth), 1);
+        }
+        // No move to split by (see `divide`).
+        assert!(divide(&board, 3).is_empty());
+    }
+
+    // The published counts the game-end and pass conventions first matter at.
+    #[test]
+    fn deep_opening_counts() {
+        assert_eq!(perft(&Board::new(), 9), 3_005_288);
+        assert_eq!(perft(&Board::new(), 10), 24_571_284);
+    }
+
+    // Ten times depth 10's work, too slow for every run. Run with:
+    //   cargo test --lib perft::tests::depth_eleven -- --ignored
+    #[test]
+    #[ignore]
+    fn depth_eleven() {
+        assert_eq!(perft(&Board::new(), 11), 212_258_800);
+    }
+}
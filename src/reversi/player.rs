@@ -5,14 +5,21 @@ pub mod alphabeta4;
 pub mod alphabeta42;
 pub mod alphabeta5;
 pub mod best;
+pub mod book;
 pub mod cli;
+pub mod eval;
+pub mod level;
+pub mod mcts;
 pub mod random;
+pub mod search;
 
 use crate::reversi::bitboard;
 use crate::reversi::rand;
 use crate::reversi::util;
 use crate::reversi::{H, W};
 use std::cmp::max;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 /// Trait for reversi player.
 /// It can decide the next move and say their name.
@@ -21,4 +28,96 @@ pub trait Player {
     // None is pass (allowed only if there is no valid moves).
     fn next(&mut self, board: &bitboard::Board) -> Option<bitboard::Mask>;
     fn name(&self) -> &'static str;
+    // Attaches a handle to stop and observe `next` (None detaches it). Players
+    // that cannot be interrupted ignore it.
+    fn set_control(&mut self, _control: Option<SearchControl>) {}
+    // The handle attached by `set_control`, if the player keeps one.
+    fn control(&self) -> Option<SearchControl> {
+        None
+    }
+}
+
+/// A snapshot of a search in progress, reported once per completed
+/// iterative-deepening depth (and once for a finished endgame solve).
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: usize,
+    /// Score of `best_move` for the side to move: the engine's evaluation, or
+    /// the final disk difference when `solved` (only its sign, 1, 0 or -1,
+    /// when the solve was for win/loss/draw).
+    pub score: i32,
+    pub best_move: bitboard::Mask,
+    /// Nodes searched so far for this move (by the calling thread).
+    pub nodes: u64,
+    /// The expected line, starting with `best_move`; None is a pass.
+    pub pv: Vec<Option<bitboard::Mask>>,
+    pub solved: bool,
+}
+
+/// Handle for cancelling and watching a player's search from outside, e.g.
+/// from a UI thread. Clones share the same stop flag and callback.
+#[derive(Clone, Default)]
+pub struct SearchControl {
+    stop: Arc<AtomicBool>,
+    progress: Option<Progress>,
+}
+
+type Progress = Arc<dyn Fn(&SearchInfo) + Send + Sync>;
+
+impl SearchControl {
+    pub fn new() -> SearchControl {
+        SearchControl::default()
+    }
+
+    /// A control that calls `progress` with every `SearchInfo`.
+    pub fn with_progress(progress: impl Fn(&SearchInfo) + Send + Sync + 'static) -> SearchControl {
+        SearchControl { stop: Arc::default(), progress: Some(Arc::new(progress)) }
+    }
+
+    /// Asks the search to stop as soon as possible. `next` then returns the best
+    /// move found so far (still a legal one). The flag stays raised, cutting
+    /// every later search short too, until `resume`.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.stop.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// A control sharing this one's stop flag that reports to this one's
+    /// callback, if any, and then to `listener`.
+    pub fn with_listener(
+        &self,
+        listener: impl Fn(&SearchInfo) + Send + Sync + 'static,
+    ) -> SearchControl {
+        let previous = self.progress.clone();
+        let progress = move |info: &SearchInfo| {
+            if let Some(previous) = &previous {
+                previous(info);
+            }
+            listener(info);
+        };
+        SearchControl { stop: Arc::clone(&self.stop), progress: Some(Arc::new(progress)) }
+    }
+
+    /// The shared stop flag, for a search to poll.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// True if anyone is listening, so a search can skip building reports.
+    pub fn wants_progress(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    pub fn report(&self, info: &SearchInfo) {
+        if let Some(progress) = &self.progress {
+            progress(info);
+        }
+    }
 }
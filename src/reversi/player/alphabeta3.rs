@@ -1,9 +1,7 @@
 use crate::reversi::bitboard::*;
-use crate::reversi::hash::FxBuildHasher;
+use crate::reversi::player::eval::{frontier_counts, phase, positional, stable_full, Evaluator};
+use crate::reversi::player::search::{Endgame, SearchConfig, Searcher};
 use crate::reversi::player::*;
-use crate::reversi::{H, W};
-use std::cmp::min;
-use std::collections::HashMap;
 
 /// Player by alpha-beta search, evolved from `AlphaBeta2Player`. It keeps the
 /// enhanced phase-dependent evaluation (positional + mobility + frontier +
@@ -18,59 +16,25 @@ use std::collections::HashMap;
 ///
 /// It is intentionally a separate `Player` so it can be measured head-to-head
 /// against `AlphaBeta2` in the `benches/league.rs` round-robin.
-pub struct AlphaBeta3Player {
-    rand: rand::Xor128,
-    weights: PhaseWeights,
-}
-
-// Two plies deeper than the depth-7 baseline. Depth must stay *odd*: this static
-// evaluation has a strong even/odd (tempo) bias, and an even search depth (e.g. 8)
-// actually plays worse than 7. Depth 9 keeps the good parity while searching
-// genuinely deeper; affordable thanks to the faster TT + PVS.
-const SEARCH_DEPTH: usize = 9;
-
-// When this few empty cells remain, switch to an exact endgame solver. Wider
-// than the baseline's 10 (WLD prunes very hard, and the faster search absorbs it).
-const ENDGAME_EMPTIES: u32 = 12;
-
-// A enough large evaluate value.
-const INF: i32 = 100_000_000;
-
-// The four corner cells, tried first during move ordering.
-const CORNERS: Mask = 0b_10000001_00000000_00000000_00000000_00000000_00000000_00000000_10000001;
-
-// Files A (leftmost) and H (rightmost) columns. Used to stop horizontal bit
-// shifts from wrapping around row boundaries when smearing into neighbours.
-const NOT_FILE_A: Mask = 0xFEFEFEFEFEFEFEFE;
-const NOT_FILE_H: Mask = 0x7F7F7F7F7F7F7F7F;
-
-/// Kind of value stored in a transposition table entry, w.r.t. the search window.
-#[derive(Clone, Copy)]
-enum Bound {
-    Exact,
-    Lower,
-    Upper,
-}
-
-#[derive(Clone, Copy)]
-struct SearchEntry {
-    depth: u8,
-    value: i32,
-    bound: Bound,
-    best_move: Mask,
-}
-
-#[derive(Clone, Copy)]
-struct SolveEntry {
-    value: i32,
-    bound: Bound,
-    best_move: Mask,
-}
-
-// Transposition tables keyed by the raw (black, white) bitmasks, hashed with the
-// fast FxHasher rather than the default SipHash.
-type SearchTt = HashMap<(Mask, Mask), SearchEntry, FxBuildHasher>;
-type SolveTt = HashMap<(Mask, Mask), SolveEntry, FxBuildHasher>;
+pub struct AlphaBeta3Player(Searcher<PhaseWeights>);
+
+const CONFIG: SearchConfig = SearchConfig {
+    // Two plies deeper than the depth-7 baseline. Depth must stay *odd*: this
+    // static evaluation has a strong even/odd (tempo) bias, and an even search
+    // depth (e.g. 8) actually plays worse than 7. Depth 9 keeps the good parity
+    // while searching genuinely deeper; affordable thanks to the faster TT + PVS.
+    depth: 9,
+    // When this few empty cells remain, switch to an exact endgame solver. Wider
+    // than the baseline's 10 (WLD prunes very hard, and the faster search
+    // absorbs it).
+    endgame_empties: 12,
+    endgame: Endgame::Wld,
+    pvs: true,
+    iterative_deepening: false,
+    killers: false,
+    history: false,
+    aspiration: None,
+};
 
 /// Linear-combination weights for the evaluation terms (one set per game phase).
 #[derive(Clone, Copy)]
@@ -110,342 +74,32 @@ impl Default for PhaseWeights {
 impl PhaseWeights {
     #[inline]
     fn select(&self, empties: u32) -> &Weights {
-        if empties >= 40 {
-            &self.opening
-        } else if empties >= 20 {
-            &self.midgame
-        } else {
-            &self.endgame
+        match phase(empties) {
+            0 => &self.opening,
+            1 => &self.midgame,
+            _ => &self.endgame,
         }
     }
 }
 
-impl AlphaBeta3Player {
-    pub fn new(seed: u32) -> AlphaBeta3Player {
-        AlphaBeta3Player { rand: rand::Xor128::from_seed(seed), weights: PhaseWeights::default() }
-    }
-
-    /// Same as `new` but with an explicit weighting, for tuning experiments.
-    pub fn with_weights(seed: u32, weights: PhaseWeights) -> AlphaBeta3Player {
-        AlphaBeta3Player { rand: rand::Xor128::from_seed(seed), weights }
-    }
-
-    /// Orders the moves in `moves_mask` from most to least promising so that
-    /// alpha-beta cuts off as early as possible.
-    fn ordered_moves(
-        board: &Board,
-        moves_mask: Mask,
-        parts: &[(Mask, Mask); 4],
-        tt_move: Mask,
-    ) -> Vec<Mask> {
-        let mut scored: Vec<(i32, Mask)> = (0..H * W)
-            .map(|i| 1u64 << i)
-            .filter(|&mov| mov & moves_mask == mov)
-            .map(|mov| {
-                let score = if mov == tt_move {
-                    i32::MAX
-                } else {
-                    let child = board.flip_with_hints(mov, parts).switch();
-                    let (opp_moves, _) = child.get_valid_mask();
-                    let mut s = -(opp_moves.count_ones() as i32);
-                    if mov & CORNERS != 0 {
-                        s += 1000;
-                    }
-                    s
-                };
-                (score, mov)
-            })
-            .collect();
-        scored.sort_by(|a, b| b.0.cmp(&a.0));
-        scored.into_iter().map(|(_, mov)| mov).collect()
-    }
-
-    /// Negamax alpha-beta with principal-variation search: the first (best-ordered)
-    /// move is searched with the full window; the rest are probed with a
-    /// null window and only re-searched when they surprise us by beating alpha.
-    fn search(
-        board: &Board,
-        alpha: i32,
-        beta: i32,
-        depth: usize,
-        passed: bool,
-        w: &PhaseWeights,
-        tt: &mut SearchTt,
-    ) -> i32 {
-        debug_assert!(alpha <= beta);
-        let (black_moves, parts) = board.get_valid_mask();
-        let (white_moves, _) = board.switch().get_valid_mask();
-        if depth == 0 || (black_moves == 0 && passed) {
-            return Self::evaluate(board, &(black_moves, white_moves), w);
-        }
-        if black_moves == 0 {
-            return -Self::search(&board.switch(), -beta, -alpha, depth, true, w, tt);
-        }
-
-        let key = (board.0, board.1);
-        let mut alpha = alpha;
-        let mut beta = beta;
-        let orig_alpha = alpha;
-
-        let mut tt_move = 0;
-        if let Some(&e) = tt.get(&key) {
-            if e.depth as usize >= depth {
-                match e.bound {
-                    Bound::Exact => return e.value,
-                    Bound::Lower => alpha = max(alpha, e.value),
-                    Bound::Upper => beta = min(beta, e.value),
-                }
-                if alpha >= beta {
-                    return e.value;
-                }
-            }
-            tt_move = e.best_move;
-        }
-
-        let moves = Self::ordered_moves(board, black_moves, &parts, tt_move);
-        let mut best = -INF;
-        let mut best_move = moves[0];
-        let mut first = true;
-        for &mov in moves.iter() {
-            let child = board.flip_with_hints(mov, &parts).switch();
-            let score = if first {
-                -Self::search(&child, -beta, -alpha, depth - 1, false, w, tt)
-            } else {
-                // Null-window probe; re-search on a fail-high inside the window.
-                let s = -Self::search(&child, -alpha - 1, -alpha, depth - 1, false, w, tt);
-                if s > alpha && s < beta {
-                    -Self::search(&child, -beta, -alpha, depth - 1, false, w, tt)
-                } else {
-                    s
-                }
-            };
-            first = false;
-            if score > best {
-                best = score;
-                best_move = mov;
-            }
-            alpha = max(alpha, score);
-            if alpha >= beta {
-                break;
-            }
-        }
-
-        let bound = if best <= orig_alpha {
-            Bound::Upper
-        } else if best >= beta {
-            Bound::Lower
-        } else {
-            Bound::Exact
-        };
-        tt.insert(key, SearchEntry { depth: depth as u8, value: best, bound, best_move });
-        best
-    }
-
-    /// Exact endgame solver (WLD), also using PVS.
-    fn solve(board: &Board, alpha: i32, beta: i32, passed: bool, tt: &mut SolveTt) -> i32 {
-        debug_assert!(alpha <= beta);
-        let (my_moves, parts) = board.get_valid_mask();
-        if my_moves == 0 {
-            if passed {
-                let (me, opp) = board.count();
-                return (me as i32 - opp as i32).signum();
-            }
-            return -Self::solve(&board.switch(), -beta, -alpha, true, tt);
-        }
-
-        let key = (board.0, board.1);
-        let mut alpha = alpha;
-        let mut beta = beta;
-        let orig_alpha = alpha;
-
-        let mut tt_move = 0;
-        if let Some(&e) = tt.get(&key) {
-            match e.bound {
-                Bound::Exact => return e.value,
-                Bound::Lower => alpha = max(alpha, e.value),
-                Bound::Upper => beta = min(beta, e.value),
-            }
-            if alpha >= beta {
-                return e.value;
-            }
-            tt_move = e.best_move;
-        }
-
-        let moves = Self::ordered_moves(board, my_moves, &parts, tt_move);
-        let mut best = -INF;
-        let mut best_move = moves[0];
-        let mut first = true;
-        for &mov in moves.iter() {
-            let child = board.flip_with_hints(mov, &parts).switch();
-            let score = if first {
-                -Self::solve(&child, -beta, -alpha, false, tt)
-            } else {
-                let s = -Self::solve(&child, -alpha - 1, -alpha, false, tt);
-                if s > alpha && s < beta {
-                    -Self::solve(&child, -beta, -alpha, false, tt)
-                } else {
-                    s
-                }
-            };
-            first = false;
-            if score > best {
-                best = score;
-                best_move = mov;
-            }
-            alpha = max(alpha, score);
-            if alpha >= beta {
-                break;
-            }
-        }
-
-        let bound = if best <= orig_alpha {
-            Bound::Upper
-        } else if best >= beta {
-            Bound::Lower
-        } else {
-            Bound::Exact
-        };
-        tt.insert(key, SolveEntry { value: best, bound, best_move });
-        best
-    }
-
-    /// Positional weight of `disks` using the same hand-tuned table as the
-    /// baseline player (corners good, X/C squares bad).
-    #[inline]
-    fn positional(disks: Mask) -> i32 {
-        // https://uguisu.skr.jp/othello/5-1.html
-        const ADD30: Mask =
-            0b_10000001_00000000_00000000_00000000_00000000_00000000_00000000_10000001;
-        const SUB01: Mask =
-            0b_00011000_00000000_00011000_10111101_10111101_00011000_00000000_00011000;
-        const SUB03: Mask =
-            0b_00000000_00111100_01000010_01000010_01000010_01000010_00111100_00000000;
-        const SUB12: Mask =
-            0b_01000010_10000001_00000000_00000000_00000000_00000000_10000001_01000010;
-        const SUB16: Mask =
-            0b_00000000_01000010_00000000_00000000_00000000_00000000_01000010_00000000;
-        let mut w = 0;
-        w += ((ADD30 & disks).count_ones() * 30) as i32;
-        w -= (SUB01 & disks).count_ones() as i32;
-        w -= ((SUB03 & disks).count_ones() * 3) as i32;
-        w -= ((SUB12 & disks).count_ones() * 12) as i32;
-        w -= ((SUB16 & disks).count_ones() * 16) as i32;
-        w
-    }
-
-    /// Counts frontier disks (disks adjacent to at least one empty cell) for
-    /// black and white. Frontier disks are usually a liability.
+/// Enhanced evaluation: the positional table, mobility, frontier disks, stable
+/// disks (`stable_full`) and (late) disk count, weighted by game phase.
+impl Evaluator for PhaseWeights {
     #[inline]
-    fn frontier_counts(board: &Board) -> (u32, u32) {
-        let Board(black, white) = *board;
-        let empty = !(black | white);
-        let neighbours = ((empty << 1) & NOT_FILE_A)
-            | ((empty >> 1) & NOT_FILE_H)
-            | (empty << 8)
-            | (empty >> 8)
-            | ((empty << 9) & NOT_FILE_A)
-            | ((empty >> 9) & NOT_FILE_H)
-            | ((empty << 7) & NOT_FILE_H)
-            | ((empty >> 7) & NOT_FILE_A);
-        ((black & neighbours).count_ones(), (white & neighbours).count_ones())
-    }
-
-    /// Flood-fill stable-disk count for black and white. A disk is stable when it
-    /// can never be flipped: along each of the four axes (horizontal, vertical,
-    /// and the two diagonals) it is either on the board edge for that axis, part
-    /// of a completely filled line, or flanked by an already-stable same-colored
-    /// disk. Seeded by the corners, iterated to a fixpoint. This is a much tighter
-    /// estimate than the old corner-anchored edge-run lower bound.
-    #[inline]
-    fn stable_full(board: &Board) -> (u32, u32) {
-        let Board(black, white) = *board;
-        let empty = !(black | white);
-        const ALL: Mask = u64::MAX;
-
-        // Spread `bits` fully along one axis: `s` cells up (`<< s`, guarded by
-        // `gu`) and down (`>> s`, guarded by `gd`), 7 steps covering the board.
-        #[inline]
-        fn spread(bits: Mask, s: u32, gu: Mask, gd: Mask) -> Mask {
-            let mut r = bits;
-            let mut m = bits;
-            for _ in 0..7 {
-                m = (m << s) & gu;
-                r |= m;
-            }
-            let mut m = bits;
-            for _ in 0..7 {
-                m = (m >> s) & gd;
-                r |= m;
-            }
-            r
-        }
-
-        // Cells whose whole line (in each axis) is fully occupied = the complement
-        // of cells sharing that line with an empty.
-        let full_h = !spread(empty, 1, NOT_FILE_A, NOT_FILE_H);
-        let full_v = !spread(empty, 8, ALL, ALL);
-        let full_d = !spread(empty, 9, NOT_FILE_A, NOT_FILE_H);
-        let full_a = !spread(empty, 7, NOT_FILE_H, NOT_FILE_A);
-
-        const FILE_A: Mask = 0x0101010101010101;
-        const FILE_H: Mask = 0x8080808080808080;
-        const RANK_1: Mask = 0x00000000000000FF;
-        const RANK_8: Mask = 0xFF00000000000000;
-        const BORDER: Mask = FILE_A | FILE_H | RANK_1 | RANK_8;
-
-        // Static per-axis safety: a full line, or the board edge for that axis.
-        let sh = full_h | FILE_A | FILE_H;
-        let sv = full_v | RANK_1 | RANK_8;
-        let sd = full_d | BORDER;
-        let sa = full_a | BORDER;
-
-        #[inline]
-        fn stable_of(color: Mask, sh: Mask, sv: Mask, sd: Mask, sa: Mask) -> Mask {
-            let mut stable = 0u64;
-            loop {
-                let safe_h = sh | ((stable << 1) & NOT_FILE_A) | ((stable >> 1) & NOT_FILE_H);
-                let safe_v = sv | (stable << 8) | (stable >> 8);
-                let safe_d = sd | ((stable << 9) & NOT_FILE_A) | ((stable >> 9) & NOT_FILE_H);
-                let safe_a = sa | ((stable << 7) & NOT_FILE_H) | ((stable >> 7) & NOT_FILE_A);
-                let next = color & safe_h & safe_v & safe_d & safe_a;
-                if next == stable {
-                    return stable;
-                }
-                stable = next;
-            }
-        }
-
-        (
-            stable_of(black, sh, sv, sd, sa).count_ones(),
-            stable_of(white, sh, sv, sd, sa).count_ones(),
-        )
-    }
-
-    /// Enhanced evaluation. Higher is better for black.
-    #[inline]
-    fn evaluate(board: &Board, moves: &(Mask, Mask), w: &PhaseWeights) -> i32 {
+    fn evaluate(&self, board: &Board, moves: &(Mask, Mask)) -> i32 {
         let Board(black, white) = *board;
         let (black_moves, white_moves) = *moves;
 
-        if black_moves == 0 && white_moves == 0 {
-            let (b, w) = (black.count_ones(), white.count_ones());
-            return match b.cmp(&w) {
-                std::cmp::Ordering::Greater => INF,
-                std::cmp::Ordering::Less => -INF,
-                std::cmp::Ordering::Equal => 0,
-            };
-        }
-
         let empties = 64 - (black | white).count_ones();
-        let wt = w.select(empties);
+        let wt = self.select(empties);
 
-        let posdiff = Self::positional(black) - Self::positional(white);
+        let posdiff = positional(black) - positional(white);
         let mobdiff = black_moves.count_ones() as i32 - white_moves.count_ones() as i32;
 
-        let (bf, wf) = Self::frontier_counts(board);
+        let (bf, wf) = frontier_counts(board);
         let frontdiff = wf as i32 - bf as i32;
 
-        let (bs, ws) = Self::stable_full(board);
+        let (bs, ws) = stable_full(board);
         let stabdiff = bs as i32 - ws as i32;
 
         let diskdiff = black.count_ones() as i32 - white.count_ones() as i32;
@@ -458,100 +112,33 @@ impl AlphaBeta3Player {
     }
 }
 
-impl Player for AlphaBeta3Player {
-    fn next(&mut self, board: &Board) -> Option<Mask> {
-        let (black_moves, parts) = board.get_valid_mask();
-        if black_moves == 0 {
-            None
-        } else {
-            // Shuffle first moves so ties are broken uniformly at random.
-            let mut moves = (0..H * W)
-                .map(|i| 1 << i)
-                .filter(|&mov| mov & black_moves == mov)
-                .collect::<Vec<_>>();
-            let n = moves.len();
-            for i in 0..n - 1 {
-                moves.swap(i, i + self.rand.next() as usize % (n - i));
-            }
-
-            let (black, white) = board.count();
-            let empties = (H * W) as u32 - black - white;
-            let endgame = empties <= ENDGAME_EMPTIES;
+impl AlphaBeta3Player {
+    pub fn new(seed: u32) -> AlphaBeta3Player {
+        Self::with_weights(seed, PhaseWeights::default())
+    }
 
-            let mut search_tt = SearchTt::default();
-            let mut solve_tt = SolveTt::default();
+    /// Same as `new` but with an explicit weighting, for tuning experiments.
+    pub fn with_weights(seed: u32, weights: PhaseWeights) -> AlphaBeta3Player {
+        AlphaBeta3Player(Searcher::new(seed, "Alpha-Beta3", CONFIG, weights))
+    }
+}
 
-            let mut alpha = -INF;
-            let mut best_position = moves[0];
-            for &mov in moves.iter() {
-                let reversed = board.flip_with_hints(mov, &parts);
-                let score = if endgame {
-                    -Self::solve(&reversed.switch(), -INF, -alpha, false, &mut solve_tt)
-                } else {
-                    -Self::search(
-                        &reversed.switch(),
-                        -INF,
-                        -alpha,
-                        SEARCH_DEPTH,
-                        false,
-                        &self.weights,
-                        &mut search_tt,
-                    )
-                };
-                if score > alpha {
-                    alpha = score;
-                    best_position = mov;
-                }
-            }
-            Some(best_position)
-        }
+impl Player for AlphaBeta3Player {
+    fn next(&mut self, board: &Board) -> Option<Mask> {
+        self.0.next(board)
     }
 
     fn name(&self) -> &'static str {
-        "Alpha-Beta3"
+        self.0.name()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::reversi::bitboard::{position_to_mask, Board};
     use crate::reversi::gm::{GameManager, Winner};
     use crate::reversi::player::random::RandomPlayer;
 
-    #[test]
-    fn frontier_of_initial_board() {
-        let (b, w) = AlphaBeta3Player::frontier_counts(&Board::new());
-        assert_eq!((b, w), (2, 2));
-    }
-
-    #[test]
-    fn stable_full_detects_corners_only() {
-        let corners = position_to_mask(0, 0)
-            | position_to_mask(0, 7)
-            | position_to_mask(7, 0)
-            | position_to_mask(7, 7);
-        let board = Board(corners, 0);
-        assert_eq!(AlphaBeta3Player::stable_full(&board), (4, 0));
-    }
-
-    #[test]
-    fn stable_full_full_edge_from_corner() {
-        let mut top = 0u64;
-        for c in 0..8 {
-            top |= position_to_mask(0, c);
-        }
-        let board = Board(top, 0);
-        assert_eq!(AlphaBeta3Player::stable_full(&board), (8, 0));
-    }
-
-    #[test]
-    fn stable_full_full_board_all_stable() {
-        // A completely filled board: every disk is stable (no empty to flip into).
-        let board = Board(u64::MAX, 0);
-        assert_eq!(AlphaBeta3Player::stable_full(&board), (64, 0));
-    }
-
     // --- Health gate: AlphaBeta3 must crush the random player. ---
     // Relative strength vs the other engines is measured in `benches/league.rs`;
     // this is only a fast correctness gate. Fewer games than the depth-7 engines
@@ -1,9 +1,30 @@
 use crate::reversi::bitboard::*;
-use crate::reversi::hash::FxBuildHasher;
-use crate::reversi::player::Player;
+use crate::reversi::pattern::PatternWeights;
+use crate::reversi::player::eval::{stable_full, Evaluator};
+pub use crate::reversi::player::eval::{PhaseWeights, Weights};
+use crate::reversi::player::search::{
+    bound_of, final_score, SearchEntry, SolveEntry, CORNERS, INF,
+};
+use crate::reversi::player::{Player, SearchControl, SearchInfo};
 use crate::reversi::rand;
-use std::cmp::{max, min};
-use std::collections::HashMap;
+use crate::reversi::tt::TranspositionTable;
+use endgame::SMALL_EMPTIES;
+use std::cmp::{max, min, Reverse};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+mod endgame;
+mod probcut;
+#[cfg(not(target_arch = "wasm32"))]
+mod smp;
+#[cfg(not(target_arch = "wasm32"))]
+mod time;
+
+pub use crate::reversi::player::search::Bound;
+pub use probcut::{ProbCut, ProbCutFit};
+#[cfg(not(target_arch = "wasm32"))]
+pub use time::TimeControl;
 
 /// Player by alpha-beta search, evolved from `AlphaBeta4Player` (whose tuned
 /// `AlphaBeta4-2` weights it reuses verbatim). The *evaluation* is identical to
@@ -19,22 +40,37 @@ use std::collections::HashMap;
 ///  - killer-move and history-heuristic move ordering on top of AB4's
 ///    corner/mobility ordering,
 ///  - bit-iteration move generation into a stack buffer (no per-node `Vec`),
-///  - transposition tables carried across the whole game instead of being
-///    rebuilt every move (the evaluation is a pure function of the board, so
-///    entries stay valid), capped and cleared when they grow too large,
+///  - fixed-size transposition tables (`set_hash_size`) carried across the
+///    whole game instead of being rebuilt every move, with depth- and
+///    age-preferred replacement,
 ///  - a deeper exact endgame solver with fastest-first ordering, guarded by the
-///    same budget with a fallback to the iterative-deepening search.
+///    same budget with a fallback to the iterative-deepening search,
+///  - an optional Lazy SMP parallel search on native targets (`set_threads`);
+///    the default remains the deterministic single-threaded search,
+///  - an optional wall-clock `TimeControl` on native targets
+///    (`set_time_control`) that replaces the node budget while it is set,
+///  - an optional pattern-based evaluation (`set_pattern_weights`) in place of
+///    the linear one,
+///  - optional Multi-ProbCut pruning of the midgame search (`set_probcut`);
+///    its default parameters are fitted to the linear evaluation.
 ///
 /// Kept as a separate `Player` so it can be measured head-to-head against
 /// `AlphaBeta4-2` in `benches/duel5.rs`.
 pub struct AlphaBeta5Player {
     rand: rand::Xor128,
     weights: PhaseWeights,
+    /// Pattern tables evaluating the leaves instead of `weights`, if set.
+    pattern: Option<Arc<PatternWeights>>,
+    /// Multi-ProbCut parameters of the midgame search; `None` searches full
+    /// width.
+    probcut: Option<Arc<ProbCut>>,
     /// Node budget for a single `next()` call. Deterministic (independent of the
     /// wall clock), so results are reproducible for a fixed seed and game line.
     budget: u64,
     search_tt: SearchTt,
     solve_tt: SolveTt,
+    /// Combined size of the two transposition tables in MiB.
+    hash_mb: usize,
     /// Two killer moves per ply (moves that caused a beta cutoff at that depth
     /// from the root); tried early before the general static ordering.
     killers: [[Mask; 2]; MAX_PLY],
@@ -43,6 +79,23 @@ pub struct AlphaBeta5Player {
     history: [i64; 64],
     nodes: u64,
     aborted: bool,
+    /// Polled to abort the search: the flag of `control`, or the one the main
+    /// thread of a parallel search raises to stop its helpers.
+    stop: Option<Arc<AtomicBool>>,
+    /// Outside stop flag and progress callback (`Player::set_control`).
+    control: Option<SearchControl>,
+    /// First depth of the iterative deepening: 1, or 3 for every other helper
+    /// thread so that the threads of a parallel search spread over two depths.
+    first_depth: usize,
+    /// Helper players of a parallel search, one per extra thread (see `smp`).
+    #[cfg(not(target_arch = "wasm32"))]
+    helpers: Vec<AlphaBeta5Player>,
+    /// Wall-clock limit of `next()` instead of the node budget (see `time`).
+    #[cfg(not(target_arch = "wasm32"))]
+    time_control: Option<TimeControl>,
+    /// Deadlines of the timed move being searched.
+    #[cfg(not(target_arch = "wasm32"))]
+    timer: Option<time::MoveTimer>,
     /// Diagnostics for calibration (`benches/duel5.rs`): filled by `next()`.
     pub last_depth: usize,
     pub last_nodes: u64,
@@ -62,14 +115,21 @@ const MAX_PLY: usize = 64;
 // iterative-deepening search already sees within a few cells of the end.
 const ENDGAME_EMPTIES: u32 = 14;
 
+// A few cells earlier, a win/loss/draw solve (`solve_wld`) already proves who
+// wins: on self-play positions it averages ~350k nodes at 20 empties against
+// ~8M for the exact solve (see `calibrate_wld`). A winning or drawing move is
+// played as soon as it is proven; in a lost position the search picks the most
+// stubborn move instead.
+const WLD_EMPTIES: u32 = 20;
+
 // Aspiration half-width: after depth 5 the root searches a narrow window around
 // the previous iteration's score, which lets the interior search prune far more
 // aggressively. On a fail (score outside the window) we re-search full width.
 const ASPIRATION_DELTA: i32 = 2500;
 
-// Cap on transposition-table size (entries). Cleared past this to bound memory
-// in a long game (~40 bytes/entry, so ~40 MB per table at the cap).
-const TT_CAP: usize = 1_000_000;
+// Default combined size of the two transposition tables in MiB, split evenly
+// (~500k entries each, about what the old hash maps held before being cleared).
+const DEFAULT_HASH_MB: usize = 32;
 
 // Default per-move node budget, sized so a browser (wasm) move takes about a
 // second: native runs ~10-13M nodes/s and wasm is ~2-3x slower, so ~4M nodes is
@@ -78,80 +138,178 @@ const TT_CAP: usize = 1_000_000;
 // via `with_budget`.
 const DEFAULT_NODE_BUDGET: u64 = 4_000_000;
 
-// A enough large evaluate value.
-const INF: i32 = 100_000_000;
-
-// The four corner cells, tried first during move ordering.
-const CORNERS: Mask = 0b_10000001_00000000_00000000_00000000_00000000_00000000_00000000_10000001;
-
-// Files A (leftmost) and H (rightmost) columns. Used to stop horizontal bit
-// shifts from wrapping around row boundaries when smearing into neighbours.
-const NOT_FILE_A: Mask = 0xFEFEFEFEFEFEFEFE;
-const NOT_FILE_H: Mask = 0x7F7F7F7F7F7F7F7F;
+/// Conversion of a table entry to and from the payload and depth of a
+/// `TranspositionTable`. The payload holds the value in bits 0..32, the bound in
+/// 32..34 and the best move's square + 1 (0 for none) in 34..41.
+trait Packed: Copy {
+    /// Whether an entry only holds for the move that stored it (see
+    /// `begin_move`); older ones are ignored rather than cleared.
+    const PER_MOVE: bool;
+    /// Replacement priority of the entry for position `key`: deeper is kept.
+    fn depth(&self, key: (Mask, Mask)) -> u8;
+    fn pack(self) -> u64;
+    fn unpack(depth: u8, data: u64) -> Self;
+}
 
-/// Kind of value stored in a transposition table entry, w.r.t. the search window.
-#[derive(Clone, Copy)]
-enum Bound {
-    Exact,
-    Lower,
-    Upper,
+fn pack(value: i32, bound: Bound, best_move: Mask) -> u64 {
+    let bound = match bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    let square = if best_move == 0 { 0 } else { best_move.trailing_zeros() as u64 + 1 };
+    value as u32 as u64 | bound << 32 | square << 34
 }
 
-#[derive(Clone, Copy)]
-struct SearchEntry {
-    depth: u8,
-    value: i32,
-    bound: Bound,
-    best_move: Mask,
+fn unpack(data: u64) -> (i32, Bound, Mask) {
+    let bound = match (data >> 32) & 3 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    let square = (data >> 34) & 0x7F;
+    let best_move = if square == 0 { 0 } else { 1 << (square - 1) };
+    (data as u32 as i32, bound, best_move)
 }
 
-#[derive(Clone, Copy)]
-struct SolveEntry {
-    value: i32,
-    bound: Bound,
-    best_move: Mask,
+impl Packed for SearchEntry {
+    const PER_MOVE: bool = true;
+    fn depth(&self, _key: (Mask, Mask)) -> u8 {
+        self.depth
+    }
+    fn pack(self) -> u64 {
+        pack(self.value, self.bound, self.best_move)
+    }
+    fn unpack(depth: u8, data: u64) -> Self {
+        let (value, bound, best_move) = unpack(data);
+        SearchEntry { depth, value, bound, best_move }
+    }
 }
 
-// Transposition tables keyed by the raw (black, white) bitmasks, hashed with the
-// fast FxHasher rather than the default SipHash.
-type SearchTt = HashMap<(Mask, Mask), SearchEntry, FxBuildHasher>;
-type SolveTt = HashMap<(Mask, Mask), SolveEntry, FxBuildHasher>;
-
-/// Linear-combination weights for the evaluation terms (one set per game phase).
-#[derive(Clone, Copy)]
-pub struct Weights {
-    pub pos: i32,
-    pub mob: i32,
-    pub pmob: i32,
-    pub front: i32,
-    pub stab: i32,
-    pub disk: i32,
+impl Packed for SolveEntry {
+    const PER_MOVE: bool = false;
+    // A solve is as deep as the position has empty cells.
+    fn depth(&self, key: (Mask, Mask)) -> u8 {
+        64 - (key.0 | key.1).count_ones() as u8
+    }
+    fn pack(self) -> u64 {
+        pack(self.value, self.bound, self.best_move)
+    }
+    fn unpack(_depth: u8, data: u64) -> Self {
+        let (value, bound, best_move) = unpack(data);
+        SolveEntry { value, bound, best_move }
+    }
 }
 
-/// The three per-phase weight sets, selected by remaining empty cells.
-#[derive(Clone, Copy)]
-pub struct PhaseWeights {
-    pub opening: Weights,
-    pub midgame: Weights,
-    pub endgame: Weights,
+/// A transposition table keyed by the raw (black, white) bitmasks, holding
+/// entries of type `E`. The table itself can be shared with the other threads
+/// of a parallel search.
+struct Tt<E> {
+    table: Arc<TranspositionTable>,
+    entry: PhantomData<E>,
 }
 
-impl PhaseWeights {
+type SearchTt = Tt<SearchEntry>;
+type SolveTt = Tt<SolveEntry>;
+
+impl<E: Packed> Tt<E> {
+    fn with_size_mb(mb: usize) -> Self {
+        Tt { table: Arc::new(TranspositionTable::with_size_mb(mb)), entry: PhantomData }
+    }
+
+    /// A handle on the same table.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    fn share(&self) -> Self {
+        Tt { table: Arc::clone(&self.table), entry: PhantomData }
+    }
+
     #[inline]
-    fn select(&self, empties: u32) -> &Weights {
-        if empties >= 40 {
-            &self.opening
-        } else if empties >= 20 {
-            &self.midgame
+    fn get(&self, key: &(Mask, Mask)) -> Option<E> {
+        let hit = if E::PER_MOVE {
+            self.table.probe_fresh(key.0, key.1)
         } else {
-            &self.endgame
+            self.table.probe(key.0, key.1)
+        };
+        hit.map(|(data, depth)| E::unpack(depth, data))
+    }
+
+    #[inline]
+    fn insert(&mut self, key: (Mask, Mask), entry: E) {
+        self.table.store(key.0, key.1, entry.depth(key), entry.pack());
+    }
+
+    fn remove(&mut self, key: &(Mask, Mask)) {
+        self.table.remove(key.0, key.1);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Ages the entries stored so far (see `TranspositionTable::new_search`).
+    fn new_search(&mut self) {
+        self.table.new_search();
+    }
+}
+
+/// Limits for one `analyze` call. The node budget plays the same role as the
+/// player's own budget in `next()`; `max_depth` additionally caps the (odd)
+/// iterative-deepening depth, e.g. for a quick, shallow hint display.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchLimits {
+    pub nodes: u64,
+    pub max_depth: usize,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        SearchLimits { nodes: DEFAULT_NODE_BUDGET, max_depth: MAX_DEPTH }
+    }
+}
+
+/// The engine's verdict on one root move, as returned by `analyze`.
+#[derive(Clone, Copy, Debug)]
+pub struct MoveEval {
+    /// The move (a single-bit mask in the mover's perspective).
+    pub mov: Mask,
+    /// Score from the mover's point of view: evaluation units from the
+    /// depth-limited search, or the exact final disk difference when `solved`.
+    pub score: i32,
+    /// Whether `score` is exact or only a bound (an aspiration re-search that
+    /// ran out of budget leaves the bound from the failed probe).
+    pub bound: Bound,
+    /// Deepest iteration that produced `score` (the number of empty cells when
+    /// `solved`).
+    pub depth: usize,
+    /// Nodes spent on this move's subtree, summed over all iterations.
+    pub nodes: u64,
+    /// True iff `score` comes from the exact endgame solver.
+    pub solved: bool,
+}
+
+/// Game result under perfect play for the side to move, from `solve_wld`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    /// The outcome of a final disk difference (or any bound of the same sign).
+    fn of(score: i32) -> Outcome {
+        match score.signum() {
+            1 => Outcome::Win,
+            -1 => Outcome::Loss,
+            _ => Outcome::Draw,
         }
     }
 }
 
 /// AB4-2's tuned weights (`pmob = 20/20/10`, `pos = 140/140/110` over AB4's
 /// defaults). See `alphabeta42.rs` for the tuning history.
-fn tuned_weights() -> PhaseWeights {
+pub fn tuned_weights() -> PhaseWeights {
     PhaseWeights {
         opening: Weights { pos: 140, mob: 20, pmob: 20, front: 35, stab: 40, disk: 0 },
         midgame: Weights { pos: 140, mob: 15, pmob: 20, front: 25, stab: 70, disk: 0 },
@@ -164,23 +322,480 @@ impl AlphaBeta5Player {
         Self::with_budget(seed, DEFAULT_NODE_BUDGET)
     }
 
+    /// Same as `new` but with explicit evaluation weights, e.g. from `tuner`.
+    pub fn with_weights(seed: u32, weights: PhaseWeights) -> AlphaBeta5Player {
+        let mut player = Self::new(seed);
+        player.weights = weights;
+        player
+    }
+
     /// Same as `new` but with an explicit node budget, for calibration / duels.
     pub fn with_budget(seed: u32, budget: u64) -> AlphaBeta5Player {
         AlphaBeta5Player {
             rand: rand::Xor128::from_seed(seed),
             weights: tuned_weights(),
+            pattern: None,
+            probcut: None,
             budget,
-            search_tt: SearchTt::default(),
-            solve_tt: SolveTt::default(),
+            search_tt: SearchTt::with_size_mb(DEFAULT_HASH_MB / 2),
+            solve_tt: SolveTt::with_size_mb(DEFAULT_HASH_MB / 2),
+            hash_mb: DEFAULT_HASH_MB,
             killers: [[0; 2]; MAX_PLY],
             history: [0; 64],
             nodes: 0,
             aborted: false,
+            stop: None,
+            control: None,
+            first_depth: 1,
+            #[cfg(not(target_arch = "wasm32"))]
+            helpers: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            time_control: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            timer: None,
             last_depth: 0,
             last_nodes: 0,
         }
     }
 
+    /// Evaluates the search's leaves with `pattern` tables instead of the
+    /// linear evaluation, or with the linear one again when `None`. Game-over
+    /// leaves are always scored as won, lost or drawn.
+    pub fn set_pattern_weights(&mut self, pattern: Option<Arc<PatternWeights>>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        for helper in self.helpers.iter_mut() {
+            helper.pattern = pattern.clone();
+        }
+        self.pattern = pattern;
+    }
+
+    /// Prunes the midgame search with Multi-ProbCut under `probcut`, e.g.
+    /// `ProbCut::default()` for the linear evaluation, or searches full width
+    /// again when `None` (the default). The endgame solvers are never
+    /// affected.
+    pub fn set_probcut(&mut self, probcut: Option<Arc<ProbCut>>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        for helper in self.helpers.iter_mut() {
+            helper.probcut = probcut.clone();
+        }
+        self.probcut = probcut;
+    }
+
+    pub fn probcut(&self) -> Option<&ProbCut> {
+        self.probcut.as_deref()
+    }
+
+    /// Resizes the transposition tables to `mb` MiB in total (split evenly
+    /// between the search and the solve table), emptying them.
+    pub fn set_hash_size(&mut self, mb: usize) {
+        self.hash_mb = mb;
+        self.search_tt = SearchTt::with_size_mb(mb / 2);
+        self.solve_tt = SolveTt::with_size_mb(mb / 2);
+        #[cfg(not(target_arch = "wasm32"))]
+        for helper in self.helpers.iter_mut() {
+            helper.search_tt = self.search_tt.share();
+            helper.solve_tt = self.solve_tt.share();
+        }
+    }
+
+    /// Combined size of the transposition tables in MiB.
+    pub fn hash_size(&self) -> usize {
+        self.hash_mb
+    }
+
+    /// Per-move housekeeping shared by `next()` and `analyze`: starts a new age
+    /// in both tables, decays the history and forgets the killers.
+    fn begin_move(&mut self) {
+        // Search entries must not outlive their move: their values come from the
+        // tempo-biased static evaluation, so an entry stored at one move's search
+        // parity is not a valid substitute at a later move's differing parity
+        // (this is why AB4 rebuilds its table each move). They are ignored from
+        // the next age on and replaced first. The solve table is safe to carry
+        // over — it stores the *exact* final disk difference, an intrinsic
+        // property of the position with no depth/parity dependence — so its old
+        // entries stay usable until replaced.
+        self.search_tt.new_search();
+        self.solve_tt.new_search();
+        self.reset_heuristics();
+    }
+
+    /// The move-ordering half of `begin_move`: decays the history and forgets
+    /// the killers. Helper threads only do this part (the tables are shared).
+    fn reset_heuristics(&mut self) {
+        for h in self.history.iter_mut() {
+            *h /= 2;
+        }
+        self.killers = [[0; 2]; MAX_PLY];
+    }
+
+    /// Counts a node and returns true (setting `aborted`) if the search must
+    /// stop: the node budget is spent, or, checked every 1024 nodes, the main
+    /// thread of a parallel search has raised the stop flag or a timed move's
+    /// deadline has passed.
+    #[inline]
+    fn out_of_budget(&mut self) -> bool {
+        self.nodes += 1;
+        let stopped = self.nodes & 1023 == 0
+            && (self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+                || self.past_deadline());
+        if self.nodes >= self.budget || stopped {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    /// True once the deadline of a timed move has passed; never under a node
+    /// budget.
+    #[inline]
+    fn past_deadline(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timer) = &self.timer {
+            return timer.expired();
+        }
+        false
+    }
+
+    /// True once a timed move is past its soft limit, so no new iteration
+    /// should be started; never under a node budget.
+    fn past_soft_limit(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timer) = &self.timer {
+            return timer.soft_expired();
+        }
+        false
+    }
+
+    /// Lets an endgame solve of a timed move run only to the soft limit
+    /// (`true`), or the search up to the hard one (`false`).
+    fn stop_at_soft_limit(&mut self, _soft: bool) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timer) = &mut self.timer {
+            timer.stop_at_soft(_soft);
+        }
+    }
+
+    /// The legal moves in `moves_mask` as single-bit masks, shuffled once so
+    /// ties are broken uniformly at random.
+    fn root_moves(&mut self, moves_mask: Mask) -> Vec<Mask> {
+        let mut moves: Vec<Mask> = Vec::new();
+        let mut m = moves_mask;
+        while m != 0 {
+            moves.push(m & m.wrapping_neg());
+            m &= m - 1;
+        }
+        let n = moves.len();
+        for i in 0..n - 1 {
+            moves.swap(i, i + self.rand.next() as usize % (n - i));
+        }
+        moves
+    }
+
+    /// Scores *every* legal move of the side to move (`board.0`), best first.
+    /// Unlike `next()`, no root move is probed with a null window, so each score
+    /// is a real value (or an explicit bound) rather than just "worse than the
+    /// best", which is what hint displays and move-quality reports need. The
+    /// search is the same iterative deepening as `next()`, restricted by
+    /// `limits`; with few enough empty cells each move is solved exactly
+    /// instead. Returns an empty vector when the side to move must pass. The
+    /// midgame scores are only full-width minimax values with ProbCut off
+    /// (the default).
+    pub fn analyze(&mut self, board: &Board, limits: &SearchLimits) -> Vec<MoveEval> {
+        let black_moves = legal_moves(board.0, board.1);
+        if black_moves == 0 {
+            return Vec::new();
+        }
+        self.begin_move();
+        let moves = self.root_moves(black_moves);
+        let mut evals: Vec<MoveEval> = moves
+            .iter()
+            .map(|&mov| MoveEval {
+                mov,
+                score: 0,
+                bound: Bound::Exact,
+                depth: 0,
+                nodes: 0,
+                solved: false,
+            })
+            .collect();
+
+        let saved_budget = self.budget;
+        self.budget = limits.nodes;
+        self.nodes = 0;
+        self.aborted = false;
+
+        let empties = 64 - (board.0 | board.1).count_ones();
+        let solved = empties <= ENDGAME_EMPTIES && self.analyze_solve(board, &mut evals);
+        if !solved {
+            self.nodes = 0;
+            self.aborted = false;
+            self.analyze_search(board, limits.max_depth, &mut evals);
+        }
+
+        self.budget = saved_budget;
+        self.last_depth = evals.iter().map(|e| e.depth).max().unwrap_or(0);
+        self.last_nodes = self.nodes;
+        // Stable, so equal scores keep their (randomised) root order.
+        evals.sort_by_key(|e| Reverse(e.score));
+
+        // Record the root like `next()` does, so `principal_variation` can start
+        // from the best move. Only an exact, full-depth best score qualifies.
+        let best = evals[0];
+        if best.bound == Bound::Exact && evals.iter().all(|e| e.depth == best.depth) {
+            let key = (board.0, board.1);
+            if best.solved {
+                let entry =
+                    SolveEntry { value: best.score, bound: Bound::Exact, best_move: best.mov };
+                self.solve_tt.insert(key, entry);
+                self.settle_solve_line(board, best.score);
+            } else {
+                let entry = SearchEntry {
+                    depth: best.depth as u8,
+                    value: best.score,
+                    bound: Bound::Exact,
+                    best_move: best.mov,
+                };
+                self.search_tt.insert(key, entry);
+            }
+        }
+        evals
+    }
+
+    /// Endgame half of `analyze`: solves every root move exactly with a full
+    /// window. Returns false (leaving `evals` untouched apart from node counts)
+    /// if the budget ran out, so the caller can fall back to the search.
+    fn analyze_solve(&mut self, board: &Board, evals: &mut [MoveEval]) -> bool {
+        let empties = 64 - (board.0 | board.1).count_ones();
+        let mut scores = Vec::with_capacity(evals.len());
+        for e in evals.iter_mut() {
+            let start = self.nodes;
+            let child = board.flip(e.mov).switch();
+            let score = -self.solve(&child, -INF, INF, false);
+            e.nodes += self.nodes - start;
+            if self.aborted {
+                return false;
+            }
+            scores.push(score);
+        }
+        for (e, score) in evals.iter_mut().zip(scores) {
+            e.score = score;
+            e.bound = Bound::Exact;
+            e.depth = empties as usize;
+            e.solved = true;
+        }
+        true
+    }
+
+    /// Midgame half of `analyze`: iterative deepening over odd depths up to
+    /// `max_depth`, searching each root move with its own aspiration window
+    /// around its previous score. When the budget runs out mid-iteration the
+    /// moves already searched keep their deeper score, the rest keep the last
+    /// completed one; a move whose re-search was cut short reports the bound
+    /// from its failed probe.
+    fn analyze_search(&mut self, board: &Board, max_depth: usize, evals: &mut [MoveEval]) {
+        let mut depth = 1;
+        while depth <= max_depth.min(MAX_DEPTH) {
+            for e in evals.iter_mut() {
+                let child = board.flip(e.mov).switch();
+                let mut alpha = if depth >= 5 { e.score - ASPIRATION_DELTA } else { -INF };
+                let mut beta = if depth >= 5 { e.score + ASPIRATION_DELTA } else { INF };
+                let mut result = None;
+                let start = self.nodes;
+                loop {
+                    let score = -self.search(&child, -beta, -alpha, depth, 0, false);
+                    if self.aborted {
+                        break;
+                    }
+                    if score <= alpha && alpha > -INF {
+                        result = Some((score, Bound::Upper));
+                        alpha = -INF; // fail-low: widen down and re-search
+                    } else if score >= beta && beta < INF {
+                        result = Some((score, Bound::Lower));
+                        beta = INF; // fail-high: widen up and re-search
+                    } else {
+                        result = Some((score, Bound::Exact));
+                        break;
+                    }
+                }
+                e.nodes += self.nodes - start;
+                if let Some((score, bound)) = result {
+                    e.score = score;
+                    e.bound = bound;
+                    e.depth = depth;
+                }
+                if self.aborted {
+                    return;
+                }
+            }
+            // Best-first for the next iteration, so the principal moves fill the
+            // search table before the rest are looked at.
+            evals.sort_by_key(|e| Reverse(e.score));
+            depth += 2;
+        }
+    }
+
+    /// Passes a finished iteration (or solve) of the root `board` to the
+    /// progress callback, if there is one. The line is the move alone where the
+    /// tables only hold bounds on it, as after a win/loss/draw solve.
+    fn report(&self, board: &Board, depth: usize, score: i32, best_move: Mask, solved: bool) {
+        if let Some(control) = self.control.as_ref().filter(|c| c.wants_progress()) {
+            let mut pv = self.principal_variation(board);
+            if pv.first() != Some(&Some(best_move)) {
+                pv = vec![Some(best_move)];
+            }
+            control.report(&SearchInfo { depth, score, best_move, nodes: self.nodes, pv, solved });
+        }
+    }
+
+    /// `think`, on the helper threads too when there are any.
+    fn think_any(&mut self, board: &Board, _black_moves: Mask, moves: Vec<Mask>) -> Mask {
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.helpers.is_empty() {
+            return self.think_parallel(board, _black_moves, moves);
+        }
+        self.think(board, moves)
+    }
+
+    /// Picks the move to play among `moves`, the shuffled legal moves of
+    /// `board`. With few enough empty cells that is the exact endgame solve, a
+    /// few cells earlier a proven win or draw. Otherwise, and whenever a solve
+    /// runs out of budget or proves a loss, it is the node-budget iterative
+    /// deepening. This is all of `next()` after the per-move housekeeping, and
+    /// what each thread of a parallel search runs.
+    fn think(&mut self, board: &Board, mut moves: Vec<Mask>) -> Mask {
+        let (black, white) = board.count();
+        let empties = 64 - black - white;
+
+        self.nodes = 0;
+        self.aborted = false;
+
+        // Endgame: one exact, disk-differential pass, budget-guarded. If it runs
+        // out of budget we fall back to the iterative-deepening search below.
+        if empties <= ENDGAME_EMPTIES {
+            self.stop_at_soft_limit(true);
+            let mut alpha = -INF;
+            let mut best_position = moves[0];
+            for &mov in moves.iter() {
+                let child = board.flip(mov).switch();
+                let score = -self.solve(&child, -INF, -alpha, false);
+                if self.aborted {
+                    break;
+                }
+                if score > alpha {
+                    alpha = score;
+                    best_position = mov;
+                }
+            }
+            if !self.aborted {
+                // The root is never stored by `solve` itself; record it so the
+                // principal variation can be walked from here.
+                self.solve_tt.insert(
+                    (board.0, board.1),
+                    SolveEntry { value: alpha, bound: Bound::Exact, best_move: best_position },
+                );
+                let last_nodes = self.nodes;
+                self.settle_solve_line(board, alpha);
+                self.last_depth = empties as usize;
+                self.last_nodes = last_nodes;
+                self.report(board, empties as usize, alpha, best_position, true);
+                return best_position;
+            }
+            // Solve timed out: reset for a fresh-budget iterative-deepening pass.
+            self.aborted = false;
+            self.nodes = 0;
+            self.stop_at_soft_limit(false);
+        } else if empties <= WLD_EMPTIES {
+            self.stop_at_soft_limit(true);
+            let proven = self.think_wld(board, &moves);
+            self.stop_at_soft_limit(false);
+            if let Some(mov) = proven {
+                return mov;
+            }
+            self.aborted = false;
+            self.nodes = 0;
+        }
+
+        // Iterative deepening over odd depths. Each iteration re-orders the root
+        // moves best-first for the next, deeper pass, and (from depth 5 on)
+        // searches a narrow aspiration window around the previous score so the
+        // interior search prunes hard. The search stops when the node budget is
+        // exhausted; we then play the best move from the deepest *completed*
+        // iteration.
+        let mut best_position = moves[0];
+        let mut reached = 0;
+        let mut prev_score = 0;
+        let mut depth = self.first_depth;
+        while depth <= MAX_DEPTH {
+            // Aspirate around the previous score once it is stable; re-search with
+            // a wider window on a fail-low/high until the score lands inside.
+            let mut alpha = if depth >= 5 { prev_score - ASPIRATION_DELTA } else { -INF };
+            let mut beta = if depth >= 5 { prev_score + ASPIRATION_DELTA } else { INF };
+            let iteration = loop {
+                match self.root_pvs(board, &moves, depth, alpha, beta) {
+                    None => break None, // budget exhausted mid-iteration
+                    Some((score, best_move, scored)) => {
+                        if score <= alpha && alpha > -INF {
+                            alpha = -INF; // fail-low: widen down and re-search
+                            continue;
+                        }
+                        if score >= beta && beta < INF {
+                            beta = INF; // fail-high: widen up and re-search
+                            continue;
+                        }
+                        break Some((score, best_move, scored));
+                    }
+                }
+            };
+            let Some((score, best_move, scored)) = iteration else { break };
+            // `root_pvs` does not store the root; record it (the score is exact
+            // once the aspiration loop settles) so the PV starts from here.
+            self.search_tt.insert(
+                (board.0, board.1),
+                SearchEntry { depth: depth as u8, value: score, bound: Bound::Exact, best_move },
+            );
+            prev_score = score;
+            best_position = best_move;
+            moves = scored.into_iter().map(|(_, mov)| mov).collect();
+            reached = depth;
+            self.report(board, depth, score, best_move, false);
+            depth += 2;
+            if self.past_soft_limit() {
+                break;
+            }
+        }
+
+        self.last_depth = reached;
+        self.last_nodes = self.nodes;
+        best_position
+    }
+
+    /// Win/loss/draw pass over the root `moves` for `think`: the first move
+    /// proven to win, else a drawing one, reported as solved with the outcome
+    /// (1 or 0) as its score. `None` if every move loses (the margin then
+    /// matters, which the search judges better) or the budget ran out.
+    fn think_wld(&mut self, board: &Board, moves: &[Mask]) -> Option<Mask> {
+        let empties = 64 - (board.0 | board.1).count_ones();
+        let mut chosen = None;
+        for &mov in moves {
+            let score = -self.solve(&board.flip(mov).switch(), -1, 1, false);
+            if self.aborted {
+                return None;
+            }
+            match Outcome::of(score) {
+                Outcome::Win => {
+                    chosen = Some((mov, 1));
+                    break;
+                }
+                Outcome::Draw => chosen = chosen.or(Some((mov, 0))),
+                Outcome::Loss => {}
+            }
+        }
+        self.last_depth = empties as usize;
+        self.last_nodes = self.nodes;
+        let (mov, outcome) = chosen?;
+        self.report(board, empties as usize, outcome, mov, true);
+        Some(mov)
+    }
+
     /// Records a beta cutoff for move ordering: promotes `mov` into the killer
     /// slots for `ply` and bumps its history score. Corner and TT moves are
     /// already ordered first, so they are excluded to keep the killer slots for
@@ -258,12 +873,7 @@ impl AlphaBeta5Player {
         ply: usize,
         passed: bool,
     ) -> i32 {
-        if self.aborted {
-            return 0;
-        }
-        self.nodes += 1;
-        if self.nodes >= self.budget {
-            self.aborted = true;
+        if self.aborted || self.out_of_budget() {
             return 0;
         }
         debug_assert!(alpha <= beta);
@@ -271,7 +881,11 @@ impl AlphaBeta5Player {
         let black_moves = legal_moves(board.0, board.1);
         if depth == 0 || (black_moves == 0 && passed) {
             let white_moves = legal_moves(board.1, board.0);
-            return Self::evaluate(board, &(black_moves, white_moves), &self.weights);
+            return match &self.pattern {
+                Some(pattern) if black_moves | white_moves != 0 => pattern.evaluate(board),
+                _ if black_moves | white_moves == 0 => final_score(board),
+                _ => self.weights.evaluate(board, &(black_moves, white_moves)),
+            };
         }
         if black_moves == 0 {
             return -self.search(&board.switch(), -beta, -alpha, depth, ply, true);
@@ -283,7 +897,7 @@ impl AlphaBeta5Player {
         let orig_alpha = alpha;
 
         let mut tt_move = 0;
-        if let Some(&e) = self.search_tt.get(&key) {
+        if let Some(e) = self.search_tt.get(&key) {
             if e.depth as usize >= depth {
                 match e.bound {
                     Bound::Exact => return e.value,
@@ -297,6 +911,16 @@ impl AlphaBeta5Player {
             tt_move = e.best_move;
         }
 
+        // Null-window nodes may be cut by a shallow search (see `probcut`).
+        if beta - alpha == 1 {
+            if let Some(bound) = self.probcut_cut(board, alpha, beta, depth, ply) {
+                return bound;
+            }
+            if self.aborted {
+                return 0;
+            }
+        }
+
         let mut buf = [(0i32, 0u64); MAX_MOVES];
         let n = self.order_moves(board, black_moves, tt_move, ply, depth, &mut buf);
 
@@ -332,13 +956,7 @@ impl AlphaBeta5Player {
 
         // Never store a result computed under an abort (its value is garbage).
         if !self.aborted {
-            let bound = if best <= orig_alpha {
-                Bound::Upper
-            } else if best >= beta {
-                Bound::Lower
-            } else {
-                Bound::Exact
-            };
+            let bound = bound_of(best, orig_alpha, beta);
             self.search_tt
                 .insert(key, SearchEntry { depth: depth as u8, value: best, bound, best_move });
         }
@@ -396,6 +1014,18 @@ impl AlphaBeta5Player {
         n
     }
 
+    /// Win/loss/draw endgame result for the side to move (`board.0`) under
+    /// perfect play by both sides, or `None` if the node budget ran out. Solved
+    /// with the window `(-1, 1)` around a draw, which only has to prove the sign
+    /// of the final disk difference and so costs a fraction of `solve_exact`.
+    /// Use `solve_exact` when the margin itself is needed.
+    pub fn solve_wld(&mut self, board: &Board) -> Option<Outcome> {
+        self.nodes = 0;
+        self.aborted = false;
+        let v = self.solve(board, -1, 1, false);
+        (!self.aborted).then(|| Outcome::of(v))
+    }
+
     /// Exact endgame result for the side to move (`board.0`): the final disk
     /// difference (me − opp) under perfect play by both sides, or `None` if the
     /// node budget ran out before the exact tree was exhausted. A positive value
@@ -409,19 +1039,119 @@ impl AlphaBeta5Player {
         if self.aborted {
             None
         } else {
+            self.settle_solve_line(board, v);
             Some(v)
         }
     }
 
+    /// The principal variation from `board` (side to move = `board.0`) as left
+    /// behind by the last `next()`, `analyze` or `solve_exact`: the line both
+    /// sides are expected to play, with `None` for a pass. It is read back from
+    /// the transposition tables, preferring the solve table (whose lines run to
+    /// the end of the game) and otherwise following the search table for at most
+    /// the depth that search reached. Empty if `board` was not searched. Every
+    /// move is legal when replayed with `Board::flip`.
+    ///
+    /// To get the line behind one `analyze` entry, prepend `e.mov` to the
+    /// variation of the position after it.
+    pub fn principal_variation(&self, board: &Board) -> Vec<Option<Mask>> {
+        let mut pv = Vec::new();
+        let mut board = board.clone();
+        // Score the line must keep (negated every ply), fixed by the first entry.
+        let mut expected: Option<i32> = None;
+        // Plies the search table may still vouch for, fixed by the first
+        // search-table entry on the line (its depth).
+        let mut search_plies: Option<usize> = None;
+        loop {
+            let moves = legal_moves(board.0, board.1);
+            if moves == 0 {
+                if legal_moves(board.1, board.0) == 0 {
+                    break; // game over
+                }
+                pv.push(None);
+                board = board.switch();
+                expected = expected.map(|v| -v);
+                continue;
+            }
+            let key = (board.0, board.1);
+            let step = match self.solve_tt.get(&key) {
+                Some(e) if on_line(e.value, e.bound, expected) => (e.best_move, e.value),
+                _ => match self.search_tt.get(&key) {
+                    Some(e) if on_line(e.value, e.bound, expected) => {
+                        let left = search_plies.get_or_insert(e.depth as usize);
+                        if *left == 0 {
+                            break; // beyond the horizon of the search
+                        }
+                        *left -= 1;
+                        (e.best_move, e.value)
+                    }
+                    _ => break,
+                },
+            };
+            let (mov, value) = step;
+            if mov.count_ones() != 1 || mov & moves == 0 {
+                break;
+            }
+            pv.push(Some(mov));
+            board = board.flip(mov).switch();
+            expected = Some(-value);
+        }
+        // A trailing pass is only meaningful if a move follows it.
+        while pv.last() == Some(&None) {
+            pv.pop();
+        }
+        pv
+    }
+
+    /// Makes the solve table hold an exact entry at every node of the principal
+    /// variation from `board`, whose exact score `value` is already known. The
+    /// solver's window at a PV node can be tightened by the table to exactly the
+    /// node's score, leaving only a bound (with an arbitrary move) behind; such a
+    /// node is re-solved on its own with a window just around `value`, which is
+    /// cheap with the table warm. Stops quietly if the budget runs out (the line
+    /// is then just shorter).
+    fn settle_solve_line(&mut self, board: &Board, value: i32) {
+        let mut board = board.clone();
+        let mut value = value;
+        loop {
+            if legal_moves(board.0, board.1) == 0 {
+                if legal_moves(board.1, board.0) == 0 {
+                    return; // game over
+                }
+                board = board.switch();
+                value = -value;
+                continue;
+            }
+            let key = (board.0, board.1);
+            let settled =
+                |tt: &SolveTt| tt.get(&key).filter(|e| e.bound == Bound::Exact && e.value == value);
+            if settled(&self.solve_tt).is_none() {
+                // Drop the bound first, or its probe would narrow the window again.
+                self.solve_tt.remove(&key);
+                if 64 - (board.0 | board.1).count_ones() <= SMALL_EMPTIES {
+                    self.settle_small(&board, value);
+                } else {
+                    self.solve(&board, value - 1, value + 1, false);
+                }
+                if self.aborted {
+                    return;
+                }
+            }
+            let Some(e) = settled(&self.solve_tt) else { return };
+            board = board.flip(e.best_move).switch();
+            value = -value;
+        }
+    }
+
     /// Exact endgame solver (PVS), returning the exact final *disk difference*
-    /// (my disks − opp disks). Budget-guarded like `search`.
+    /// (my disks − opp disks). Budget-guarded like `search`. The last
+    /// `SMALL_EMPTIES` cells are left to the kernels in `endgame`, which keep
+    /// nothing in the solve table.
     fn solve(&mut self, board: &Board, alpha: i32, beta: i32, passed: bool) -> i32 {
-        if self.aborted {
-            return 0;
+        if 64 - (board.0 | board.1).count_ones() <= SMALL_EMPTIES {
+            return self.solve_small(board.0, board.1, alpha, beta, passed);
         }
-        self.nodes += 1;
-        if self.nodes >= self.budget {
-            self.aborted = true;
+        if self.aborted || self.out_of_budget() {
             return 0;
         }
         debug_assert!(alpha <= beta);
@@ -441,7 +1171,7 @@ impl AlphaBeta5Player {
         let orig_alpha = alpha;
 
         let mut tt_move = 0;
-        if let Some(&e) = self.solve_tt.get(&key) {
+        if let Some(e) = self.solve_tt.get(&key) {
             match e.bound {
                 Bound::Exact => return e.value,
                 Bound::Lower => alpha = max(alpha, e.value),
@@ -453,6 +1183,24 @@ impl AlphaBeta5Player {
             tt_move = e.best_move;
         }
 
+        // Stability cutoff: stable disks keep their colour to the end, so the
+        // opponent's stable disks cap the final score at `64 - 2 * stable` and
+        // the mover's floor it at `2 * stable - 64`. The plain disk counts bound
+        // these in turn, so stability is only computed when a cut is possible.
+        // Cuts about an eighth of the solver's nodes on self-play endgames.
+        let (my_disks, opp_disks) = board.count();
+        if alpha >= 64 - 2 * opp_disks as i32 || beta <= 2 * my_disks as i32 - 64 {
+            let (my_stable, opp_stable) = stable_full(board);
+            let upper = 64 - 2 * opp_stable as i32;
+            if upper <= alpha {
+                return upper;
+            }
+            let lower = 2 * my_stable as i32 - 64;
+            if lower >= beta {
+                return lower;
+            }
+        }
+
         // Fastest-first ordering (fewest opponent replies), skipping the mobility
         // computation in the last few plies where the subtree is tiny.
         let empties = 64 - (board.0 | board.1).count_ones();
@@ -510,179 +1258,33 @@ impl AlphaBeta5Player {
         }
 
         if !self.aborted {
-            let bound = if best <= orig_alpha {
-                Bound::Upper
-            } else if best >= beta {
-                Bound::Lower
-            } else {
-                Bound::Exact
-            };
+            let bound = bound_of(best, orig_alpha, beta);
             self.solve_tt.insert(key, SolveEntry { value: best, bound, best_move });
         }
         best
     }
 
-    /// Positional weight of `disks` using the same hand-tuned table as the
-    /// baseline player (corners good, X/C squares bad).
-    #[inline]
-    fn positional(disks: Mask) -> i32 {
-        // https://uguisu.skr.jp/othello/5-1.html
-        const ADD30: Mask =
-            0b_10000001_00000000_00000000_00000000_00000000_00000000_00000000_10000001;
-        const SUB01: Mask =
-            0b_00011000_00000000_00011000_10111101_10111101_00011000_00000000_00011000;
-        const SUB03: Mask =
-            0b_00000000_00111100_01000010_01000010_01000010_01000010_00111100_00000000;
-        const SUB12: Mask =
-            0b_01000010_10000001_00000000_00000000_00000000_00000000_10000001_01000010;
-        const SUB16: Mask =
-            0b_00000000_01000010_00000000_00000000_00000000_00000000_01000010_00000000;
-        let mut w = 0;
-        w += ((ADD30 & disks).count_ones() * 30) as i32;
-        w -= (SUB01 & disks).count_ones() as i32;
-        w -= ((SUB03 & disks).count_ones() * 3) as i32;
-        w -= ((SUB12 & disks).count_ones() * 12) as i32;
-        w -= ((SUB16 & disks).count_ones() * 16) as i32;
-        w
-    }
-
-    /// Smears `disks` into all eight neighbouring directions, guarding the
-    /// horizontal shifts against wrapping across row boundaries.
-    #[inline]
-    fn neighbours(disks: Mask) -> Mask {
-        ((disks << 1) & NOT_FILE_A)
-            | ((disks >> 1) & NOT_FILE_H)
-            | (disks << 8)
-            | (disks >> 8)
-            | ((disks << 9) & NOT_FILE_A)
-            | ((disks >> 9) & NOT_FILE_H)
-            | ((disks << 7) & NOT_FILE_H)
-            | ((disks >> 7) & NOT_FILE_A)
-    }
-
-    /// Counts frontier disks (disks adjacent to at least one empty cell) for
-    /// black and white. Frontier disks are usually a liability.
-    #[inline]
-    fn frontier_counts(board: &Board) -> (u32, u32) {
-        let Board(black, white) = *board;
-        let empty = !(black | white);
-        let neighbours = Self::neighbours(empty);
-        ((black & neighbours).count_ones(), (white & neighbours).count_ones())
-    }
-
-    /// Potential mobility for black and white: the number of empty cells adjacent
-    /// to the *opponent's* disks — squares where each side may gain future moves.
-    #[inline]
-    fn potential_mobility(board: &Board) -> (u32, u32) {
-        let Board(black, white) = *board;
-        let empty = !(black | white);
-        let black_pmob = (Self::neighbours(white) & empty).count_ones();
-        let white_pmob = (Self::neighbours(black) & empty).count_ones();
-        (black_pmob, white_pmob)
-    }
-
-    /// Flood-fill stable-disk count for black and white. A disk is stable when it
-    /// can never be flipped: along each of the four axes it is either on the
-    /// board edge for that axis, part of a completely filled line, or flanked by
-    /// an already-stable same-colored disk. Iterated to a fixpoint.
-    #[inline]
-    fn stable_full(board: &Board) -> (u32, u32) {
-        let Board(black, white) = *board;
-        let empty = !(black | white);
-        const ALL: Mask = u64::MAX;
-
-        #[inline]
-        fn spread(bits: Mask, s: u32, gu: Mask, gd: Mask) -> Mask {
-            let mut r = bits;
-            let mut m = bits;
-            for _ in 0..7 {
-                m = (m << s) & gu;
-                r |= m;
-            }
-            let mut m = bits;
-            for _ in 0..7 {
-                m = (m >> s) & gd;
-                r |= m;
-            }
-            r
-        }
-
-        let full_h = !spread(empty, 1, NOT_FILE_A, NOT_FILE_H);
-        let full_v = !spread(empty, 8, ALL, ALL);
-        let full_d = !spread(empty, 9, NOT_FILE_A, NOT_FILE_H);
-        let full_a = !spread(empty, 7, NOT_FILE_H, NOT_FILE_A);
-
-        const FILE_A: Mask = 0x0101010101010101;
-        const FILE_H: Mask = 0x8080808080808080;
-        const RANK_1: Mask = 0x00000000000000FF;
-        const RANK_8: Mask = 0xFF00000000000000;
-        const BORDER: Mask = FILE_A | FILE_H | RANK_1 | RANK_8;
-
-        let sh = full_h | FILE_A | FILE_H;
-        let sv = full_v | RANK_1 | RANK_8;
-        let sd = full_d | BORDER;
-        let sa = full_a | BORDER;
-
-        #[inline]
-        fn stable_of(color: Mask, sh: Mask, sv: Mask, sd: Mask, sa: Mask) -> Mask {
-            let mut stable = 0u64;
-            loop {
-                let safe_h = sh | ((stable << 1) & NOT_FILE_A) | ((stable >> 1) & NOT_FILE_H);
-                let safe_v = sv | (stable << 8) | (stable >> 8);
-                let safe_d = sd | ((stable << 9) & NOT_FILE_A) | ((stable >> 9) & NOT_FILE_H);
-                let safe_a = sa | ((stable << 7) & NOT_FILE_H) | ((stable >> 7) & NOT_FILE_A);
-                let next = color & safe_h & safe_v & safe_d & safe_a;
-                if next == stable {
-                    return stable;
-                }
-                stable = next;
-            }
+    /// The default weights' evaluation, for the side to move (`board.0`): the
+    /// score the search gives a leaf. A finished game scores `±INF` or 0.
+    /// Exposed for engines that borrow it, e.g. `mcts`'s playout cut-off.
+    pub fn static_evaluation(board: &Board) -> i32 {
+        let moves = (legal_moves(board.0, board.1), legal_moves(board.1, board.0));
+        if moves.0 | moves.1 == 0 {
+            return final_score(board);
         }
-
-        (
-            stable_of(black, sh, sv, sd, sa).count_ones(),
-            stable_of(white, sh, sv, sd, sa).count_ones(),
-        )
+        tuned_weights().evaluate(board, &moves)
     }
+}
 
-    /// Enhanced evaluation. Higher is better for black.
-    #[inline]
-    fn evaluate(board: &Board, moves: &(Mask, Mask), w: &PhaseWeights) -> i32 {
-        let Board(black, white) = *board;
-        let (black_moves, white_moves) = *moves;
-
-        if black_moves == 0 && white_moves == 0 {
-            let (b, w) = (black.count_ones(), white.count_ones());
-            return match b.cmp(&w) {
-                std::cmp::Ordering::Greater => INF,
-                std::cmp::Ordering::Less => -INF,
-                std::cmp::Ordering::Equal => 0,
-            };
-        }
-
-        let empties = 64 - (black | white).count_ones();
-        let wt = w.select(empties);
-
-        let posdiff = Self::positional(black) - Self::positional(white);
-        let mobdiff = black_moves.count_ones() as i32 - white_moves.count_ones() as i32;
-
-        let (bpm, wpm) = Self::potential_mobility(board);
-        let pmobdiff = bpm as i32 - wpm as i32;
-
-        let (bf, wf) = Self::frontier_counts(board);
-        let frontdiff = wf as i32 - bf as i32;
-
-        let (bs, ws) = Self::stable_full(board);
-        let stabdiff = bs as i32 - ws as i32;
-
-        let diskdiff = black.count_ones() as i32 - white.count_ones() as i32;
-
-        wt.pos * posdiff
-            + wt.mob * mobdiff
-            + wt.pmob * pmobdiff
-            + wt.front * frontdiff
-            + wt.stab * stabdiff
-            + wt.disk * diskdiff
+/// Whether a table entry's best move continues a principal variation whose
+/// score so far is `expected` (`None` at its start). An exact entry's move is on
+/// the line; so is a lower bound's cutoff move when the bound equals the line's
+/// score, since a move reaching the best possible score is a best move.
+fn on_line(value: i32, bound: Bound, expected: Option<i32>) -> bool {
+    match bound {
+        Bound::Exact => expected.is_none_or(|v| v == value),
+        Bound::Lower => expected == Some(value),
+        Bound::Upper => false,
     }
 }
 
@@ -697,149 +1299,355 @@ impl Player for AlphaBeta5Player {
             return None;
         }
 
-        // The search table must be rebuilt every move: its values come from the
-        // tempo-biased static evaluation, so an entry stored at one move's search
-        // parity is not a valid substitute at a later move's differing parity
-        // (this is why AB4 also rebuilds it each move). The solve table is safe to
-        // carry over — it stores the *exact* final disk difference, an intrinsic
-        // property of the position with no depth/parity dependence — so it is only
-        // cleared to bound memory in a long game.
-        self.search_tt.clear();
-        if self.solve_tt.len() > TT_CAP {
-            self.solve_tt.clear();
-        }
-        for h in self.history.iter_mut() {
-            *h /= 2;
+        self.begin_move();
+        let moves = self.root_moves(black_moves);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(control) = self.time_control {
+            return Some(self.think_timed(control, board, black_moves, moves));
         }
-        self.killers = [[0; 2]; MAX_PLY];
+        Some(self.think_any(board, black_moves, moves))
+    }
 
-        // Root moves, shuffled once so ties are broken uniformly at random.
-        let mut moves: Vec<Mask> = Vec::new();
-        let mut m = black_moves;
-        while m != 0 {
-            moves.push(m & m.wrapping_neg());
-            m &= m - 1;
+    fn name(&self) -> &'static str {
+        "Alpha-Beta5"
+    }
+
+    fn set_control(&mut self, control: Option<SearchControl>) {
+        self.stop = control.as_ref().map(SearchControl::stop_flag);
+        self.control = control;
+    }
+
+    fn control(&self) -> Option<SearchControl> {
+        self.control.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::bitboard::Board;
+    use crate::reversi::gm::{GameManager, Winner};
+    use crate::reversi::player::random::RandomPlayer;
+    use crate::reversi::playout::{random_position, Stop};
+
+    #[test]
+    fn solve_cuts_on_the_opponents_stable_disks() {
+        // The opponent holds the full first rank (and more), which no move can
+        // flip, so the mover can finish at most 64 - 2 * 8 = 48 disks ahead.
+        // a8..g8 are empty, and the mover can take the seventh rank from there.
+        let (opp, empty) = (0x00FF_0000_0000_00FF, 0x7F00_0000_0000_0000);
+        let board = Board(!(opp | empty), opp);
+        assert_ne!(legal_moves(board.0, board.1), 0);
+        let (_, stable) = stable_full(&board);
+        let cap = 64 - 2 * stable as i32;
+        assert!(cap <= 48);
+
+        let mut ai = AlphaBeta5Player::new(1);
+        assert_eq!(ai.solve(&board, cap, cap + 1, false), cap);
+        assert_eq!(ai.nodes, 1);
+        // A window the bound cannot settle is searched as usual.
+        let mut ai = AlphaBeta5Player::new(1);
+        let exact = ai.solve(&board, -INF, INF, false);
+        assert!(exact <= cap && ai.nodes > 1);
+    }
+
+    #[test]
+    fn analyze_scores_every_legal_move() {
+        let board = Board::new();
+        let mut ai = AlphaBeta5Player::new(3);
+        let evals = ai.analyze(&board, &SearchLimits { nodes: 200_000, max_depth: MAX_DEPTH });
+
+        let legal = legal_moves(board.0, board.1);
+        assert_eq!(evals.len(), legal.count_ones() as usize);
+        assert_eq!(evals.iter().fold(0, |acc, e| acc | e.mov), legal);
+        assert!(evals.windows(2).all(|w| w[0].score >= w[1].score), "best first");
+        for e in evals.iter() {
+            assert!(!e.solved);
+            assert!(e.depth >= 1 && e.depth % 2 == 1, "odd depths only, got {}", e.depth);
+            assert!(e.nodes > 0);
         }
-        let n = moves.len();
-        for i in 0..n - 1 {
-            moves.swap(i, i + self.rand.next() as usize % (n - i));
+    }
+
+    #[test]
+    fn analyze_respects_depth_cap() {
+        let mut ai = AlphaBeta5Player::new(3);
+        let evals = ai.analyze(&Board::new(), &SearchLimits { nodes: u64::MAX, max_depth: 3 });
+        assert!(evals.iter().all(|e| e.depth == 3 && e.bound == Bound::Exact));
+        assert_eq!(ai.last_depth, 3);
+    }
+
+    #[test]
+    fn pattern_weights_replace_the_linear_evaluation() {
+        let board = random_position(5, Stop::Empties(40));
+        let limits = SearchLimits { nodes: u64::MAX, max_depth: 3 };
+        let mut ai = AlphaBeta5Player::new(5);
+        assert!(ai.analyze(&board, &limits).iter().any(|e| e.score != 0));
+
+        // All-zero tables score every non-final leaf 0.
+        ai.set_pattern_weights(Some(Arc::new(PatternWeights::new())));
+        assert!(ai.analyze(&board, &limits).iter().all(|e| e.score == 0));
+        let mov = ai.next(&board).unwrap();
+        assert_ne!(mov & legal_moves(board.0, board.1), 0);
+
+        ai.set_pattern_weights(None);
+        assert!(ai.analyze(&board, &limits).iter().any(|e| e.score != 0));
+    }
+
+    #[test]
+    fn analyze_solves_endgame_exactly() {
+        for seed in 0..4 {
+            let board = random_position(seed, Stop::Empties(10));
+            let mut ai = AlphaBeta5Player::new(seed);
+            let evals = ai.analyze(&board, &SearchLimits::default());
+            assert!(evals.iter().all(|e| e.solved && e.bound == Bound::Exact && e.depth == 10));
+
+            // Every move's score is the exact solve of the position after it.
+            for e in evals.iter() {
+                let child = board.flip(e.mov).switch();
+                let exact = AlphaBeta5Player::new(seed).solve_exact(&child).unwrap();
+                assert_eq!(e.score, -exact);
+            }
+            let best = AlphaBeta5Player::new(seed).solve_exact(&board).unwrap();
+            assert_eq!(evals[0].score, best);
         }
+    }
 
-        let (black, white) = board.count();
-        let empties = 64 - black - white;
+    #[test]
+    fn wld_solve_agrees_with_the_exact_sign() {
+        for seed in 0..6 {
+            let board = random_position(seed, Stop::Empties(12));
+            let exact = AlphaBeta5Player::new(seed).solve_exact(&board).unwrap();
+            let wld = AlphaBeta5Player::new(seed).solve_wld(&board).unwrap();
+            assert_eq!(wld, Outcome::of(exact), "seed {seed}");
+        }
+        let mut ai = AlphaBeta5Player::with_budget(1, 10);
+        assert_eq!(ai.solve_wld(&random_position(1, Stop::Empties(12))), None);
+    }
 
-        self.nodes = 0;
-        self.aborted = false;
+    #[test]
+    fn next_plays_a_proven_win_from_the_wld_horizon() {
+        for seed in 0..6 {
+            let board = random_position(seed, Stop::Empties(WLD_EMPTIES));
+            let mut ai = AlphaBeta5Player::new(seed);
+            let Some(outcome) = ai.solve_wld(&board) else { continue };
+            let mov = ai.next(&board).unwrap();
+            if outcome != Outcome::Loss {
+                assert_eq!(ai.last_depth, WLD_EMPTIES as usize);
+                let reply = AlphaBeta5Player::new(seed).solve_wld(&board.flip(mov).switch());
+                let expected = if outcome == Outcome::Win { Outcome::Loss } else { Outcome::Draw };
+                assert_eq!(reply, Some(expected), "seed {seed}");
+            }
+        }
+    }
 
-        // Endgame: one exact, disk-differential pass, budget-guarded. If it runs
-        // out of budget we fall back to the iterative-deepening search below.
-        if empties <= ENDGAME_EMPTIES {
-            let mut alpha = -INF;
-            let mut best_position = moves[0];
-            for &mov in moves.iter() {
-                let child = board.flip(mov).switch();
-                let score = -self.solve(&child, -INF, -alpha, false);
-                if self.aborted {
-                    break;
-                }
-                if score > alpha {
-                    alpha = score;
-                    best_position = mov;
+    // Calibration (run explicitly): nodes for a WLD against an exact solve of
+    // self-play positions, to place WLD_EMPTIES. Run with:
+    //   cargo test --release --lib alphabeta5::tests::calibrate_wld -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn calibrate_wld() {
+        for empties in [16, 18, 20, 22] {
+            let (mut wld, mut exact) = (0, 0);
+            for seed in 0..8 {
+                let mut ai = AlphaBeta5Player::with_budget(seed, 20_000);
+                let mut board = Board::new();
+                while 64 - (board.0 | board.1).count_ones() > empties {
+                    board = match ai.next(&board) {
+                        Some(mov) => board.flip(mov).switch(),
+                        None => board.switch(),
+                    };
                 }
+                let mut solver = AlphaBeta5Player::with_budget(seed, u64::MAX);
+                solver.solve_wld(&board);
+                wld += solver.nodes;
+                // Capped, so the deepest exact figure is only a lower bound.
+                let mut solver = AlphaBeta5Player::with_budget(seed, 20_000_000);
+                solver.solve_exact(&board);
+                exact += solver.nodes;
             }
-            if !self.aborted {
-                self.last_depth = empties as usize;
-                self.last_nodes = self.nodes;
-                return Some(best_position);
-            }
-            // Solve timed out: reset for a fresh-budget iterative-deepening pass.
-            self.aborted = false;
-            self.nodes = 0;
+            println!("{empties} empties: wld {} exact {} nodes/position", wld / 8, exact / 8);
         }
+    }
 
-        // Iterative deepening over odd depths. Each iteration re-orders the root
-        // moves best-first for the next, deeper pass, and (from depth 5 on)
-        // searches a narrow aspiration window around the previous score so the
-        // interior search prunes hard. The search stops when the node budget is
-        // exhausted; we then play the best move from the deepest *completed*
-        // iteration.
-        let mut best_position = moves[0];
-        let mut reached = 0;
-        let mut prev_score = 0;
-        let mut depth = 1;
-        while depth <= MAX_DEPTH {
-            // Aspirate around the previous score once it is stable; re-search with
-            // a wider window on a fail-low/high until the score lands inside.
-            let mut alpha = if depth >= 5 { prev_score - ASPIRATION_DELTA } else { -INF };
-            let mut beta = if depth >= 5 { prev_score + ASPIRATION_DELTA } else { INF };
-            let iteration = loop {
-                match self.root_pvs(board, &moves, depth, alpha, beta) {
-                    None => break None, // budget exhausted mid-iteration
-                    Some((score, best_move, scored)) => {
-                        if score <= alpha && alpha > -INF {
-                            alpha = -INF; // fail-low: widen down and re-search
-                            continue;
-                        }
-                        if score >= beta && beta < INF {
-                            beta = INF; // fail-high: widen up and re-search
-                            continue;
-                        }
-                        break Some((score, best_move, scored));
-                    }
+    #[test]
+    fn packed_entries_round_trip() {
+        let e = SearchEntry { depth: 13, value: -INF, bound: Bound::Upper, best_move: 1 << 63 };
+        let u = SearchEntry::unpack(13, e.pack());
+        assert_eq!((u.depth, u.value, u.bound, u.best_move), (13, -INF, Bound::Upper, 1 << 63));
+        assert!(e.pack() < 1 << crate::reversi::tt::PAYLOAD_BITS);
+
+        let e = SolveEntry { value: 64, bound: Bound::Lower, best_move: 0 };
+        let u = SolveEntry::unpack(0, e.pack());
+        assert_eq!((u.value, u.bound, u.best_move), (64, Bound::Lower, 0));
+    }
+
+    #[test]
+    fn search_entries_expire_with_the_move_but_solve_entries_do_not() {
+        let mut ai = AlphaBeta5Player::new(1);
+        ai.set_hash_size(2);
+        assert_eq!(ai.hash_size(), 2);
+        let key = (0b11, 0b100);
+        let search = SearchEntry { depth: 5, value: 7, bound: Bound::Exact, best_move: 0 };
+        let solve = SolveEntry { value: 7, bound: Bound::Exact, best_move: 0 };
+        ai.search_tt.insert(key, search);
+        ai.solve_tt.insert(key, solve);
+        assert!(ai.search_tt.get(&key).is_some());
+        ai.begin_move();
+        assert!(ai.search_tt.get(&key).is_none());
+        assert_eq!(ai.solve_tt.get(&key).map(|e| e.value), Some(7));
+    }
+
+    /// Replays `pv` from `board` through `Board::flip`, asserting each move is
+    /// legal and each pass forced, and returns the board reached.
+    fn replay(board: &Board, pv: &[Option<Mask>]) -> Board {
+        let mut board = board.clone();
+        for (ply, &mov) in pv.iter().enumerate() {
+            let moves = legal_moves(board.0, board.1);
+            match mov {
+                Some(mov) => {
+                    assert_eq!(mov.count_ones(), 1, "ply {ply}: not a single move");
+                    assert_ne!(mov & moves, 0, "ply {ply}: illegal move");
+                    board = board.flip(mov).switch();
                 }
-            };
-            let Some((score, best_move, scored)) = iteration else { break };
-            prev_score = score;
-            best_position = best_move;
-            moves = scored.into_iter().map(|(_, mov)| mov).collect();
-            reached = depth;
-            depth += 2;
+                None => {
+                    assert_eq!(moves, 0, "ply {ply}: pass with a legal move");
+                    board = board.switch();
+                }
+            }
         }
+        board
+    }
 
-        self.last_depth = reached;
-        self.last_nodes = self.nodes;
-        Some(best_position)
+    #[test]
+    fn principal_variation_after_next_starts_with_the_move() {
+        let board = random_position(5, Stop::Empties(40));
+        let mut ai = AlphaBeta5Player::with_budget(5, 300_000);
+        let mov = ai.next(&board).unwrap();
+        let pv = ai.principal_variation(&board);
+        assert_eq!(pv.first(), Some(&Some(mov)));
+        assert!(pv.len() <= ai.last_depth, "PV longer than the search: {}", pv.len());
+        replay(&board, &pv);
     }
 
-    fn name(&self) -> &'static str {
-        "Alpha-Beta5"
+    #[test]
+    fn principal_variation_after_solve_reaches_the_solved_score() {
+        for seed in 0..4 {
+            let board = random_position(seed, Stop::Empties(12));
+            let mut ai = AlphaBeta5Player::new(seed);
+            let exact = ai.solve_exact(&board).unwrap();
+            let pv = ai.principal_variation(&board);
+
+            // The line is played to the very end and produces exactly the score.
+            let end = replay(&board, &pv);
+            assert!(!end.continues(), "PV must run to the end of the game");
+            let (a, b) = end.count();
+            let diff = a as i32 - b as i32;
+            // Each ply (move or pass) flips the perspective once.
+            let diff = if pv.len().is_multiple_of(2) { diff } else { -diff };
+            assert_eq!(diff, exact);
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::reversi::bitboard::{position_to_mask, Board};
-    use crate::reversi::gm::{GameManager, Winner};
-    use crate::reversi::player::random::RandomPlayer;
+    #[test]
+    fn principal_variation_of_unsearched_board_is_empty() {
+        let ai = AlphaBeta5Player::new(1);
+        assert!(ai.principal_variation(&Board::new()).is_empty());
+    }
+
+    fn recording_control() -> (SearchControl, Arc<std::sync::Mutex<Vec<SearchInfo>>>) {
+        let infos = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&infos);
+        let control =
+            SearchControl::with_progress(move |info| sink.lock().unwrap().push(info.clone()));
+        (control, infos)
+    }
 
     #[test]
-    fn frontier_of_initial_board() {
-        let (b, w) = AlphaBeta5Player::frontier_counts(&Board::new());
-        assert_eq!((b, w), (2, 2));
+    fn progress_is_reported_for_every_completed_depth() {
+        let board = random_position(2, Stop::Empties(40));
+        let (control, infos) = recording_control();
+        let mut ai = AlphaBeta5Player::with_budget(2, 100_000);
+        ai.set_control(Some(control));
+        let mov = ai.next(&board).unwrap();
+
+        let infos = infos.lock().unwrap();
+        let depths: Vec<usize> = infos.iter().map(|i| i.depth).collect();
+        assert_eq!(depths, (1..=ai.last_depth).step_by(2).collect::<Vec<_>>());
+        let last = infos.last().unwrap();
+        assert_eq!(last.best_move, mov);
+        assert_eq!(last.pv.first(), Some(&Some(mov)));
+        assert!(!last.solved);
+        assert!(infos.windows(2).all(|w| w[0].nodes <= w[1].nodes));
     }
 
     #[test]
-    fn potential_mobility_initial_board_symmetric() {
-        let (b, w) = AlphaBeta5Player::potential_mobility(&Board::new());
-        assert_eq!(b, w);
-        assert!(b > 0);
+    fn progress_reports_a_finished_solve() {
+        let board = random_position(4, Stop::Empties(10));
+        let (control, infos) = recording_control();
+        let mut ai = AlphaBeta5Player::new(4);
+        ai.set_control(Some(control));
+        let mov = ai.next(&board).unwrap();
+
+        let infos = infos.lock().unwrap();
+        assert_eq!(infos.len(), 1);
+        assert!(infos[0].solved);
+        assert_eq!(infos[0].best_move, mov);
+        assert_eq!(Some(infos[0].score), AlphaBeta5Player::new(4).solve_exact(&board));
     }
 
     #[test]
-    fn stable_full_detects_corners_only() {
-        let corners = position_to_mask(0, 0)
-            | position_to_mask(0, 7)
-            | position_to_mask(7, 0)
-            | position_to_mask(7, 7);
-        let board = Board(corners, 0);
-        assert_eq!(AlphaBeta5Player::stable_full(&board), (4, 0));
+    fn progress_reports_a_win_loss_draw_solve() {
+        let mut checked = 0;
+        for seed in 0..6 {
+            let board = random_position(seed, Stop::Empties(WLD_EMPTIES));
+            let outcome = AlphaBeta5Player::new(seed).solve_wld(&board);
+            let score = match outcome {
+                Some(Outcome::Win) => 1,
+                Some(Outcome::Draw) => 0,
+                _ => continue,
+            };
+            let (control, infos) = recording_control();
+            let mut ai = AlphaBeta5Player::new(seed);
+            ai.set_control(Some(control));
+            let mov = ai.next(&board).unwrap();
+
+            let infos = infos.lock().unwrap();
+            assert_eq!(infos.len(), 1, "seed {seed}");
+            assert!(infos[0].solved);
+            assert_eq!((infos[0].best_move, infos[0].score), (mov, score));
+            assert_eq!(infos[0].pv.first(), Some(&Some(mov)));
+            checked += 1;
+        }
+        assert!(checked > 0);
     }
 
     #[test]
-    fn stable_full_full_board_all_stable() {
-        let board = Board(u64::MAX, 0);
-        assert_eq!(AlphaBeta5Player::stable_full(&board), (64, 0));
+    fn stop_cuts_the_search_short_with_a_legal_move() {
+        let board = random_position(6, Stop::Empties(40));
+        let control = SearchControl::new();
+        let mut ai = AlphaBeta5Player::with_budget(6, u64::MAX);
+        ai.set_control(Some(control.clone()));
+        let mov = std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                control.stop();
+            });
+            ai.next(&board).unwrap()
+        });
+        assert_ne!(mov & legal_moves(board.0, board.1), 0);
+        assert!(ai.last_depth < MAX_DEPTH);
+
+        // Still raised: the next search stops at once, until resumed.
+        assert!(control.is_stopped());
+        ai.next(&board).unwrap();
+        assert!(ai.last_nodes < 100_000);
+        control.resume();
+        ai.set_control(None);
+        let mut reference = AlphaBeta5Player::with_budget(6, 20_000);
+        let mut ai = AlphaBeta5Player::with_budget(6, 20_000);
+        ai.set_control(Some(control));
+        assert_eq!(ai.next(&board), reference.next(&board));
     }
 
     // --- Health gate: AlphaBeta5 must crush the random player. ---
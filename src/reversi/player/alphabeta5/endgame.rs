@@ -0,0 +1,202 @@
+//! Specialised solver kernels for the last few empty cells.
+//!
+//! Near the end the general solver spends most of its time on bookkeeping
+//! rather than search: it generates the full move mask, sorts a move buffer by
+//! opponent mobility and probes and stores the solve table at every node, for
+//! subtrees of a handful of positions. With `SMALL_EMPTIES` or fewer cells
+//! left, `solve` hands over to these kernels instead:
+//!
+//! - `solve_small` tries the empty cells directly (a cell is a move iff it
+//!   flips something), in parity order: cells in a quadrant with an odd number
+//!   of empties first, since moving there tends to leave the opponent the
+//!   last, unanswered move of that region. No table, no buffer.
+//! - `solve_last` scores the final empty cell by counting the disks the move
+//!   flips, without making it; it is not counted as a node.
+
+use super::{AlphaBeta5Player, Bound, SolveEntry, INF};
+use crate::reversi::bitboard::{flip_disks, legal_moves, Board, Mask};
+
+/// Empty cells from which `solve` uses the kernels.
+pub(super) const SMALL_EMPTIES: u32 = 5;
+
+// The four 4x4 quadrants of the board.
+const QUADRANTS: [Mask; 4] =
+    [0x0000_0000_0F0F_0F0F, 0x0000_0000_F0F0_F0F0, 0x0F0F_0F0F_0000_0000, 0xF0F0_F0F0_0000_0000];
+
+impl AlphaBeta5Player {
+    /// Exact final disk difference for `me` to move against `opp`, with at
+    /// most `SMALL_EMPTIES` empty cells. Same contract as `solve`.
+    pub(super) fn solve_small(
+        &mut self,
+        me: Mask,
+        opp: Mask,
+        alpha: i32,
+        beta: i32,
+        passed: bool,
+    ) -> i32 {
+        let empty = !(me | opp);
+        if empty.count_ones() == 1 && !self.aborted {
+            return solve_last(me, opp, empty);
+        }
+        if self.aborted || self.out_of_budget() {
+            return 0;
+        }
+
+        let odd = QUADRANTS
+            .iter()
+            .filter(|&&q| (empty & q).count_ones() % 2 == 1)
+            .fold(0, |odd, q| odd | q);
+        let mut alpha = alpha;
+        let mut best = -INF;
+        for cells in [empty & odd, empty & !odd] {
+            let mut m = cells;
+            while m != 0 {
+                let mov = m & m.wrapping_neg();
+                m &= m - 1;
+                let flips = flip_disks(me, opp, mov);
+                if flips == 0 {
+                    continue;
+                }
+                let score = -self.solve_small(opp ^ flips, me | mov | flips, -beta, -alpha, false);
+                if self.aborted {
+                    return best;
+                }
+                if score > best {
+                    best = score;
+                    if best > alpha {
+                        alpha = best;
+                        if alpha >= beta {
+                            return best;
+                        }
+                    }
+                }
+            }
+        }
+        if best == -INF {
+            // No move: pass, or the game is over.
+            if passed {
+                return me.count_ones() as i32 - opp.count_ones() as i32;
+            }
+            return -self.solve_small(opp, me, -beta, -alpha, true);
+        }
+        best
+    }
+
+    /// Stores an exact solve-table entry for `board` (at most `SMALL_EMPTIES`
+    /// empty cells, side to move has a move), whose exact score `value` is
+    /// known, with a move that achieves it. The kernels store nothing, so this
+    /// is how the principal variation is carried to the end of the game.
+    pub(super) fn settle_small(&mut self, board: &Board, value: i32) {
+        let mut m = legal_moves(board.0, board.1);
+        while m != 0 {
+            let mov = m & m.wrapping_neg();
+            m &= m - 1;
+            let child = board.flip(mov).switch();
+            let score = -self.solve_small(child.0, child.1, -value - 1, -value + 1, false);
+            if self.aborted {
+                return;
+            }
+            if score == value {
+                let entry = SolveEntry { value, bound: Bound::Exact, best_move: mov };
+                self.solve_tt.insert((board.0, board.1), entry);
+                return;
+            }
+        }
+    }
+}
+
+/// Final disk difference for `me` to move with `cell` the only empty cell: it
+/// is played by `me` if it flips anything, else by the opponent if it flips
+/// anything for them, else the game ends with it empty.
+#[inline]
+fn solve_last(me: Mask, opp: Mask, cell: Mask) -> i32 {
+    let disks = me.count_ones() as i32 - opp.count_ones() as i32;
+    let flips = flip_disks(me, opp, cell).count_ones() as i32;
+    if flips > 0 {
+        return disks + 2 * flips + 1;
+    }
+    let flips = flip_disks(opp, me, cell).count_ones() as i32;
+    if flips > 0 {
+        return disks - 2 * flips - 1;
+    }
+    disks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::playout::random_games;
+
+    // Plain negamax over the whole tree, for checking the kernels.
+    fn negamax(board: &Board, passed: bool) -> i32 {
+        let moves = legal_moves(board.0, board.1);
+        if moves == 0 {
+            if passed {
+                let (me, opp) = board.count();
+                return me as i32 - opp as i32;
+            }
+            return -negamax(&board.switch(), true);
+        }
+        let mut best = -INF;
+        let mut m = moves;
+        while m != 0 {
+            let mov = m & m.wrapping_neg();
+            m &= m - 1;
+            best = best.max(-negamax(&board.flip(mov).switch(), false));
+        }
+        best
+    }
+
+    // Every position of random games from 8 empty cells on, passes included.
+    fn random_endings(seed: u32, games: usize) -> Vec<Board> {
+        random_games(seed, games)
+            .into_iter()
+            .flat_map(|game| game.plies.into_iter().map(|(board, _)| board).chain([game.end]))
+            .filter(|board| 64 - (board.0 | board.1).count_ones() <= 8)
+            .collect()
+    }
+
+    #[test]
+    fn kernels_match_a_full_negamax() {
+        for board in random_endings(1, 40) {
+            let mut ai = AlphaBeta5Player::new(1);
+            assert_eq!(ai.solve_exact(&board), Some(negamax(&board, false)), "{board}");
+        }
+    }
+
+    #[test]
+    fn last_cell_goes_to_whoever_can_play_it() {
+        // A full board but for a1, with the opponent on b1..g1 and the mover
+        // everywhere else: a1 flips b1..g1 and wipes the opponent out.
+        let a1 = 1;
+        let opp = 0x7E;
+        let me = !(opp | a1);
+        assert_eq!(solve_last(me, opp, a1), 64);
+        // With the sides swapped the mover cannot play a1, so the opponent does.
+        assert_eq!(solve_last(opp, me, a1), -64);
+        // Nobody can play it: the game ends with a1 empty.
+        let me = !a1;
+        assert_eq!(solve_last(me, 0, a1), 63);
+    }
+
+    // Endgame positions from `AlphaBeta5` self-play, 10 to 18 empty cells, in
+    // the manner of the FFO test suite: (side to move, opponent, exact score as
+    // solved before the kernels existed).
+    const POSITIONS: [(Mask, Mask, i32); 7] = [
+        (0x04030fc7f3790010, 0x787cf0380c860f04, 28),
+        (0x0801036377190124, 0x20b0fc9c88e63e18, 36),
+        (0x3e3c192cb0303818, 0x0040e6530f0f0724, 28),
+        (0x181c0c0d349c1c1c, 0x24e0f0f0ca632020, 14),
+        (0xfc030fc7d3490010, 0x007cf0382cb62f04, 34),
+        (0x0801036b77192164, 0x21b6fc9488e61e18, 8),
+        (0x3e1e0d25f1211919, 0x4060f25a0e5e2664, 28),
+    ];
+
+    #[test]
+    fn self_play_endgames_keep_their_scores() {
+        for (me, opp, score) in POSITIONS {
+            let mut ai = AlphaBeta5Player::with_budget(1, u64::MAX);
+            assert_eq!(ai.solve_exact(&Board(me, opp)), Some(score));
+        }
+    }
+}
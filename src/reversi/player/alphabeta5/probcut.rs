@@ -0,0 +1,322 @@
+//! Multi-ProbCut selective search for the midgame.
+//!
+//! A deep search's score is well predicted by a shallow search of the same
+//! position: `deep ≈ a * shallow + b`, with residuals of standard deviation
+//! `sigma`. Before searching a null-window node `depth` deep, ProbCut runs the
+//! shallow search with a window chosen so that, if it fails high (low), the
+//! deep search would fail high (low) too with probability given by
+//! `threshold` standard deviations, and then cuts without the deep search.
+//! The relation differs through the game, so the parameters are fitted per
+//! depth and per game phase ("multi"), from the same phases as the evaluation
+//! weights. `ProbCut::calibrate` fits them from full-width searches of sample
+//! positions; the defaults come from such a run on `AlphaBeta5` self-play
+//! positions (see `calibrate_probcut` in the tests).
+//!
+//! Cuts are only taken in null-window nodes, so the principal variation is
+//! still searched full width, but scores are no longer exact minimax values.
+//! It is off by default; `AlphaBeta5Player::set_probcut` turns it on. The
+//! default parameters are fitted to the linear evaluation and do not carry
+//! over to pattern weights.
+
+use super::{AlphaBeta5Player, INF, MAX_DEPTH};
+use crate::reversi::bitboard::Board;
+use crate::reversi::player::eval::phase;
+use std::fmt;
+
+/// Shallowest depth ProbCut is tried at.
+const MIN_DEPTH: usize = 3;
+
+/// Game phases with their own parameters, by empty cells: at least 40, at
+/// least 20, fewer.
+const PHASES: usize = 3;
+
+// Fewest positions a fit is made from.
+const MIN_SAMPLES: usize = 10;
+
+/// Fitted relation `deep ≈ a * shallow + b` between the shallow and the deep
+/// search of one depth, with the standard deviation of its residuals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProbCutFit {
+    pub a: f64,
+    pub b: f64,
+    pub sigma: f64,
+}
+
+/// Multi-ProbCut parameters of `AlphaBeta5Player`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProbCut {
+    /// How many standard deviations the shallow search must clear the window
+    /// by. Higher is safer and prunes less.
+    pub threshold: f64,
+    // Indexed by phase, then by deep search depth.
+    fits: [[Option<ProbCutFit>; MAX_DEPTH + 1]; PHASES],
+}
+
+// (phase, depth, a, b, sigma) from `calibrate_probcut` on 736 positions of 80
+// self-play games.
+#[rustfmt::skip]
+const DEFAULT_FITS: [(usize, usize, f64, f64, f64); 27] = [
+    (0, 3, 1.013, -45.8, 235.6),
+    (0, 4, 1.017, -20.7, 232.0),
+    (0, 5, 1.013, -27.5, 195.4),
+    (0, 6, 1.032, -6.3, 338.1),
+    (0, 7, 1.063, -10.0, 314.8),
+    (0, 8, 1.070, 13.2, 344.0),
+    (0, 9, 1.087, 37.3, 333.0),
+    (0, 10, 1.092, 29.4, 418.1),
+    (0, 11, 1.127, 39.5, 490.8),
+    (1, 3, 0.995, -78.5, 556.7),
+    (1, 4, 1.051, -23.9, 651.7),
+    (1, 5, 1.051, -34.4, 676.3),
+    (1, 6, 1.085, -88.3, 1074.3),
+    (1, 7, 1.103, 40.9, 1034.1),
+    (1, 8, 1.120, -82.3, 1009.5),
+    (1, 9, 1.157, 203.5, 975.8),
+    (1, 10, 1.209, -84.0, 1352.3),
+    (1, 11, 1.222, 264.9, 1283.6),
+    (2, 3, 1.081, -62.5, 780.3),
+    (2, 4, 1.112, -155.9, 877.7),
+    (2, 5, 1.131, -81.7, 1019.5),
+    (2, 6, 1.229, -308.1, 1616.1),
+    (2, 7, 1.241, -185.2, 1745.7),
+    (2, 8, 1.267, -141.0, 1889.9),
+    (2, 9, 1.347, 90.1, 2149.8),
+    (2, 10, 1.445, -311.2, 2746.7),
+    (2, 11, 1.424, 117.0, 2780.7),
+];
+
+impl Default for ProbCut {
+    fn default() -> Self {
+        let mut probcut = ProbCut::new(1.5);
+        for (phase, depth, a, b, sigma) in DEFAULT_FITS {
+            probcut.set_fit(phase, depth, Some(ProbCutFit { a, b, sigma }));
+        }
+        probcut
+    }
+}
+
+/// Depth of the shallow search for a `depth`-deep one: about half as deep,
+/// with the same parity, since the evaluation is biased by whose turn it is.
+pub(super) fn shallow_depth(depth: usize) -> usize {
+    let shallow = depth.div_ceil(2);
+    if (depth - shallow) % 2 == 1 {
+        shallow - 1
+    } else {
+        shallow
+    }
+}
+
+impl ProbCut {
+    /// No fits, so nothing is cut until some are set.
+    pub fn new(threshold: f64) -> ProbCut {
+        ProbCut { threshold, fits: [[None; MAX_DEPTH + 1]; PHASES] }
+    }
+
+    pub fn set_fit(&mut self, phase: usize, depth: usize, fit: Option<ProbCutFit>) {
+        self.fits[phase][depth] = fit;
+    }
+
+    /// The fit for a `depth`-deep search with `empties` empty cells. Depths
+    /// beyond the calibrated ones use the deepest fit of the same parity.
+    pub fn fit(&self, empties: u32, depth: usize) -> Option<ProbCutFit> {
+        let fits = &self.fits[phase(empties)];
+        (MIN_DEPTH..=depth.min(MAX_DEPTH)).rev().step_by(2).find_map(|d| fits[d])
+    }
+
+    /// Fits the parameters of every depth up to `max_depth` from full-width
+    /// searches of `positions` (side to move = `.0`): for each position the
+    /// shallow and the deep search of every depth, by least squares per phase.
+    /// Expensive; depth 9 or so is as deep as is practical.
+    pub fn calibrate(positions: &[Board], max_depth: usize, threshold: f64) -> ProbCut {
+        let max_depth = max_depth.min(MAX_DEPTH);
+        // (shallow, deep) score pairs per phase and depth.
+        let mut pairs = vec![vec![Vec::new(); max_depth + 1]; PHASES];
+        let mut ai = AlphaBeta5Player::with_budget(1, u64::MAX);
+        ai.set_probcut(None);
+        for board in positions {
+            ai.begin_move();
+            // Increasing depths, so the table never answers for a deeper search
+            // than asked, and the scores are those of each depth.
+            let scores: Vec<i32> =
+                (0..=max_depth).map(|depth| ai.search(board, -INF, INF, depth, 0, false)).collect();
+            let phase = phase(64 - (board.0 | board.1).count_ones());
+            for depth in MIN_DEPTH..=max_depth {
+                let (shallow, deep) = (scores[shallow_depth(depth)], scores[depth]);
+                // Won or lost outright: nothing to regress.
+                if shallow.abs() < INF && deep.abs() < INF {
+                    pairs[phase][depth].push((shallow as f64, deep as f64));
+                }
+            }
+        }
+
+        let mut probcut = ProbCut::new(threshold);
+        for (phase, by_depth) in pairs.iter().enumerate() {
+            for (depth, pairs) in by_depth.iter().enumerate() {
+                if pairs.len() >= MIN_SAMPLES {
+                    probcut.set_fit(phase, depth, regress(pairs));
+                }
+            }
+        }
+        probcut
+    }
+}
+
+/// Least-squares line through `pairs`, if it has a positive slope.
+fn regress(pairs: &[(f64, f64)]) -> Option<ProbCutFit> {
+    let n = pairs.len() as f64;
+    let (mx, my) = pairs.iter().fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+    let (sxx, sxy) = pairs.iter().fold((0.0, 0.0), |(sxx, sxy), (x, y)| {
+        (sxx + (x - mx) * (x - mx), sxy + (x - mx) * (y - my))
+    });
+    if sxx == 0.0 || sxy <= 0.0 {
+        return None;
+    }
+    let a = sxy / sxx;
+    let b = my - a * mx;
+    let sigma = (pairs.iter().map(|(x, y)| (y - a * x - b).powi(2)).sum::<f64>() / n).sqrt();
+    Some(ProbCutFit { a, b, sigma })
+}
+
+/// One `(phase, depth, a, b, sigma),` line per fit, as in the default table.
+impl fmt::Display for ProbCut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (phase, fits) in self.fits.iter().enumerate() {
+            for (depth, fit) in fits.iter().enumerate() {
+                if let Some(ProbCutFit { a, b, sigma }) = fit {
+                    writeln!(f, "({phase}, {depth}, {a:.3}, {b:.1}, {sigma:.1}),")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AlphaBeta5Player {
+    /// The ProbCut test of a null-window node of `search`: `Some` bound of the
+    /// window if the shallow search predicts the deep one to fail outside it.
+    pub(super) fn probcut_cut(
+        &mut self,
+        board: &Board,
+        alpha: i32,
+        beta: i32,
+        depth: usize,
+        ply: usize,
+    ) -> Option<i32> {
+        if depth < MIN_DEPTH {
+            return None;
+        }
+        let probcut = self.probcut.as_ref()?;
+        let fit = probcut.fit(64 - (board.0 | board.1).count_ones(), depth)?;
+        let margin = probcut.threshold * fit.sigma;
+        let shallow = shallow_depth(depth);
+
+        // deep >= beta is likely once a * shallow + b >= beta + margin.
+        let high = ((beta as f64 + margin - fit.b) / fit.a).ceil();
+        if high < INF as f64 {
+            let high = high as i32;
+            if self.search(board, high - 1, high, shallow, ply, false) >= high && !self.aborted {
+                return Some(beta);
+            }
+        }
+        // And deep <= alpha once a * shallow + b <= alpha - margin.
+        let low = ((alpha as f64 - margin - fit.b) / fit.a).floor();
+        if low > -INF as f64 {
+            let low = low as i32;
+            if self.search(board, low, low + 1, shallow, ply, false) <= low && !self.aborted {
+                return Some(alpha);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::player::alphabeta5::SearchLimits;
+    use crate::reversi::player::Player;
+    use crate::reversi::tuner::SelfPlay;
+    use std::sync::Arc;
+
+    // Calibration (run explicitly): fits the parameters on positions from
+    // self-play games and prints them in the form of `DEFAULT_FITS` (about ten
+    // minutes). Run with:
+    //   cargo test --release --lib probcut::tests::calibrate_probcut -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn calibrate_probcut() {
+        let games = SelfPlay { games: 80, budget: 20_000, solve_empties: 0, ..SelfPlay::default() };
+        // Every fifth position, so the samples are not all near neighbours.
+        let positions: Vec<Board> = games
+            .generate()
+            .into_iter()
+            .map(|sample| sample.board)
+            .filter(|board| 64 - (board.0 | board.1).count_ones() > 14)
+            .step_by(5)
+            .collect();
+        let start = std::time::Instant::now();
+        let probcut = ProbCut::calibrate(&positions, 11, 1.5);
+        println!("{} positions in {:?}", positions.len(), start.elapsed());
+        print!("{probcut}");
+    }
+
+    #[test]
+    fn probcut_saves_nodes_and_can_be_switched_off() {
+        // A midgame position: 20 plies of quick self-play.
+        let mut ai = AlphaBeta5Player::with_budget(1, 1_000);
+        let mut board = Board::new();
+        for _ in 0..20 {
+            board = board.flip(ai.next(&board).unwrap()).switch();
+        }
+        let limits = SearchLimits { nodes: u64::MAX, max_depth: 7 };
+        let nodes = |ai: &mut AlphaBeta5Player| -> u64 {
+            ai.analyze(&board, &limits).iter().map(|e| e.nodes).sum()
+        };
+
+        let mut full = AlphaBeta5Player::new(1);
+        assert_eq!(full.probcut(), None, "ProbCut is opt-in");
+        let mut selective = AlphaBeta5Player::new(1);
+        selective.set_probcut(Some(Arc::new(ProbCut::default())));
+        let full_nodes = nodes(&mut full);
+        assert!(nodes(&mut selective) < full_nodes);
+
+        // Without fits nothing is cut: the full-width search again.
+        let mut unfitted = AlphaBeta5Player::new(1);
+        unfitted.set_probcut(Some(Arc::new(ProbCut::new(1.5))));
+        assert_eq!(nodes(&mut unfitted), full_nodes);
+    }
+
+    #[test]
+    fn shallow_searches_keep_the_parity() {
+        for depth in MIN_DEPTH..=MAX_DEPTH {
+            let shallow = shallow_depth(depth);
+            assert!(shallow >= 1 && shallow < depth, "{depth} -> {shallow}");
+            assert_eq!((depth - shallow) % 2, 0);
+        }
+        assert_eq!(shallow_depth(9), 5);
+    }
+
+    #[test]
+    fn deeper_searches_fall_back_to_the_deepest_fit_of_their_parity() {
+        let mut probcut = ProbCut::new(1.5);
+        let fit = |a| Some(ProbCutFit { a, b: 0.0, sigma: 100.0 });
+        probcut.set_fit(1, 5, fit(1.0));
+        probcut.set_fit(1, 6, fit(2.0));
+        assert_eq!(probcut.fit(30, 3), None);
+        assert_eq!(probcut.fit(30, 5), fit(1.0));
+        assert_eq!(probcut.fit(30, 11), fit(1.0));
+        assert_eq!(probcut.fit(30, 12), fit(2.0));
+        assert_eq!(probcut.fit(50, 5), None);
+    }
+
+    #[test]
+    fn regression_recovers_a_line() {
+        let pairs: Vec<(f64, f64)> = (0..20)
+            .map(|x| (x as f64, 2.0 * x as f64 + 3.0 + if x % 2 == 0 { 1.0 } else { -1.0 }))
+            .collect();
+        let fit = regress(&pairs).unwrap();
+        assert!((fit.a - 2.0).abs() < 0.05 && (fit.b - 3.0).abs() < 0.2, "{fit:?}");
+        assert!((fit.sigma - 1.0).abs() < 0.05);
+        assert_eq!(regress(&[(1.0, 5.0); 12]), None);
+    }
+}
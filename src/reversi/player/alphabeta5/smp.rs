@@ -0,0 +1,129 @@
+//! Lazy SMP parallel search for `AlphaBeta5Player` (native targets only).
+//!
+//! Every thread runs the ordinary single-threaded iterative deepening on the
+//! same root, and the threads cooperate only through the player's lock-free
+//! transposition tables, which the helpers share: a helper that has already
+//! searched a subtree leaves its result behind for the others to cut on. The
+//! helpers are decorrelated by their own root shuffle, killers and history,
+//! and every other one starts the deepening at depth 3 instead of 1, so they
+//! run ahead of the main thread. Only the main thread's result is played; the
+//! helpers are stopped as soon as it finishes. Each thread gets the full node
+//! budget, so the wall-clock time of a move stays roughly that of the
+//! single-threaded search.
+//!
+//! The result depends on thread timing, so a parallel search is not
+//! reproducible. One thread (the default) keeps the deterministic search.
+
+use super::AlphaBeta5Player;
+use crate::reversi::bitboard::{Board, Mask};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+impl AlphaBeta5Player {
+    /// Searches with `threads` threads from now on (values below 1 count as 1).
+    /// The helpers share this player's transposition tables; going back to one
+    /// thread restores the deterministic search. Either way the tables are
+    /// emptied.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.search_tt.clear();
+        self.solve_tt.clear();
+        self.helpers = (0..threads.max(1) - 1)
+            .map(|i| {
+                // Seeded apart from `self.rand`, which must not advance here.
+                let seed = (i as u32 + 1).wrapping_mul(0x9E37_79B1);
+                let mut helper = AlphaBeta5Player::with_budget(seed, self.budget);
+                helper.weights = self.weights;
+                helper.pattern = self.pattern.clone();
+                helper.probcut = self.probcut.clone();
+                helper.search_tt = self.search_tt.share();
+                helper.solve_tt = self.solve_tt.share();
+                helper.first_depth = if i % 2 == 0 { 3 } else { 1 };
+                helper
+            })
+            .collect();
+    }
+
+    /// Number of threads `next()` searches with.
+    pub fn threads(&self) -> usize {
+        self.helpers.len() + 1
+    }
+
+    /// `think` on this thread while the helpers search the same root on their
+    /// own threads. `moves` is this player's shuffled root; each helper shuffles
+    /// `black_moves` its own way. The per-move housekeeping (`begin_move`) has
+    /// already aged the shared tables.
+    pub(super) fn think_parallel(
+        &mut self,
+        board: &Board,
+        black_moves: Mask,
+        moves: Vec<Mask>,
+    ) -> Mask {
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut helpers = std::mem::take(&mut self.helpers);
+        let best = std::thread::scope(|s| {
+            for helper in helpers.iter_mut() {
+                helper.reset_heuristics();
+                helper.budget = self.budget;
+                helper.stop = Some(Arc::clone(&stop));
+                let moves = helper.root_moves(black_moves);
+                s.spawn(move || helper.think(board, moves));
+            }
+            let best = self.think(board, moves);
+            stop.store(true, Ordering::Relaxed);
+            best
+        });
+        self.last_nodes += helpers.iter().map(|h| h.last_nodes).sum::<u64>();
+        self.helpers = helpers;
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::bitboard::legal_moves;
+    use crate::reversi::player::Player;
+    use crate::reversi::playout::{random_position, Stop};
+
+    #[test]
+    fn parallel_search_plays_legal_moves() {
+        let mut ai = AlphaBeta5Player::with_budget(1, 50_000);
+        ai.set_threads(3);
+        assert_eq!(ai.threads(), 3);
+        for seed in 0..6 {
+            let board = random_position(seed, Stop::Empties(40));
+            let mov = ai.next(&board).unwrap();
+            assert_eq!(mov.count_ones(), 1);
+            assert_ne!(mov & legal_moves(board.0, board.1), 0);
+            assert!(ai.last_depth >= 1);
+        }
+    }
+
+    #[test]
+    fn parallel_endgame_move_is_optimal() {
+        for seed in 0..4 {
+            let board = random_position(seed, Stop::Empties(12));
+            let mut ai = AlphaBeta5Player::new(seed);
+            ai.set_threads(3);
+            let mov = ai.next(&board).unwrap();
+
+            let mut solver = AlphaBeta5Player::new(seed);
+            let best = solver.solve_exact(&board).unwrap();
+            let got = -solver.solve_exact(&board.flip(mov).switch()).unwrap();
+            assert_eq!(got, best, "seed {seed}: parallel move is not a best move");
+        }
+    }
+
+    #[test]
+    fn one_thread_restores_the_deterministic_search() {
+        let mut ai = AlphaBeta5Player::with_budget(9, 30_000);
+        ai.set_threads(4);
+        ai.set_threads(1);
+        assert_eq!(ai.threads(), 1);
+        let mut reference = AlphaBeta5Player::with_budget(9, 30_000);
+        for seed in 0..4 {
+            let board = random_position(seed, Stop::Empties(44));
+            assert_eq!(ai.next(&board), reference.next(&board));
+        }
+    }
+}
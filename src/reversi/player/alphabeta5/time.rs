@@ -0,0 +1,206 @@
+//! Wall-clock time control for `AlphaBeta5Player` (native targets only).
+//!
+//! The browser build has no usable `std::time::Instant`, which is why the
+//! engine is normally limited by a node budget. Where a clock exists a
+//! `TimeControl` can replace that budget. Each move gets two limits:
+//!  - the *soft* limit is checked between iterative-deepening iterations: once
+//!    it has passed no new (and roughly branching-factor times longer)
+//!    iteration is started,
+//!  - the *hard* limit is checked inside `search` and `solve` every 1024 nodes,
+//!    alongside the stop flag, and aborts the iteration in progress.
+//!
+//! An endgame solve may only run to the soft limit, so a solve that does not
+//! finish still leaves time for the fallback search.
+
+use super::AlphaBeta5Player;
+use crate::reversi::bitboard::{Board, Mask};
+use std::time::{Duration, Instant};
+
+/// How much wall-clock time the player may spend. The clock-based variants are
+/// charged by the player itself after every move; set the control again to
+/// resynchronise with an external clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    /// A fixed time per move ("think for 2.5 s").
+    PerMove(Duration),
+    /// One clock for the rest of the game.
+    SuddenDeath { remaining: Duration },
+    /// A clock that gains `increment` after every move (Fischer).
+    Increment { remaining: Duration, increment: Duration },
+}
+
+// Kept off the clock-based hard limits to cover the move's own overhead.
+const SAFETY_MARGIN: Duration = Duration::from_millis(20);
+
+// Extra moves the clock is shared over, so it never runs dry near the end even
+// if the game lasts longer than the empties suggest (passes).
+const MOVES_RESERVE: u32 = 2;
+
+impl TimeControl {
+    /// The soft and hard limit for a move with `empties` empty cells.
+    fn limits(&self, empties: u32) -> (Duration, Duration) {
+        match *self {
+            TimeControl::PerMove(time) => (time / 2, time),
+            TimeControl::SuddenDeath { remaining } => share(remaining, Duration::ZERO, empties),
+            TimeControl::Increment { remaining, increment } => share(remaining, increment, empties),
+        }
+    }
+
+    /// Books a move that took `elapsed`.
+    fn charge(&mut self, elapsed: Duration) {
+        match self {
+            TimeControl::PerMove(_) => {}
+            TimeControl::SuddenDeath { remaining } => {
+                *remaining = remaining.saturating_sub(elapsed)
+            }
+            TimeControl::Increment { remaining, increment } => {
+                *remaining = remaining.saturating_sub(elapsed) + *increment
+            }
+        }
+    }
+}
+
+/// Spreads `remaining` evenly over this side's moves still to come, plus most
+/// of the increment. The hard limit allows three times that, but never more
+/// than half of what is left.
+fn share(remaining: Duration, increment: Duration, empties: u32) -> (Duration, Duration) {
+    let usable = remaining.saturating_sub(SAFETY_MARGIN);
+    let moves_left = empties.div_ceil(2) + MOVES_RESERVE;
+    let cap = usable / 2;
+    let soft = (usable / moves_left + increment * 3 / 4).min(cap);
+    (soft, (soft * 3).min(cap))
+}
+
+/// The deadlines of the move being searched.
+pub(super) struct MoveTimer {
+    soft: Instant,
+    hard: Instant,
+    /// What `search`/`solve` abort at: `hard`, or `soft` during an endgame solve.
+    deadline: Instant,
+}
+
+impl MoveTimer {
+    #[inline]
+    pub(super) fn expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    pub(super) fn soft_expired(&self) -> bool {
+        Instant::now() >= self.soft
+    }
+
+    /// Aborts at the soft limit (`true`) or only at the hard one (`false`).
+    pub(super) fn stop_at_soft(&mut self, soft: bool) {
+        self.deadline = if soft { self.soft } else { self.hard };
+    }
+}
+
+impl AlphaBeta5Player {
+    /// Limits every `next()` by wall-clock time instead of the node budget, or
+    /// by the node budget again when `None`. `analyze` always uses its limits.
+    pub fn set_time_control(&mut self, control: Option<TimeControl>) {
+        self.time_control = control;
+    }
+
+    /// The time control in effect, with the clock as charged so far.
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    /// `next()` under the time control: searches without a node budget until
+    /// the limits stop it, then charges the clock.
+    pub(super) fn think_timed(
+        &mut self,
+        control: TimeControl,
+        board: &Board,
+        black_moves: Mask,
+        moves: Vec<Mask>,
+    ) -> Mask {
+        let start = Instant::now();
+        let (soft, hard) = control.limits(64 - (board.0 | board.1).count_ones());
+        self.timer =
+            Some(MoveTimer { soft: start + soft, hard: start + hard, deadline: start + hard });
+        let budget = std::mem::replace(&mut self.budget, u64::MAX);
+        let best = self.think_any(board, black_moves, moves);
+        self.budget = budget;
+        self.timer = None;
+        let mut control = control;
+        control.charge(start.elapsed());
+        self.time_control = Some(control);
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::bitboard::legal_moves;
+    use crate::reversi::player::Player;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn limits_never_exceed_the_clock() {
+        for remaining in [0, 5, 100, 10_000, 600_000] {
+            for increment in [0, 10, 2_000] {
+                for empties in [1, 14, 30, 60] {
+                    let control = TimeControl::Increment {
+                        remaining: remaining * MS,
+                        increment: increment * MS,
+                    };
+                    let (soft, hard) = control.limits(empties);
+                    assert!(soft <= hard, "{control:?} at {empties}: {soft:?} > {hard:?}");
+                    assert!(hard <= (remaining * MS) / 2);
+                }
+            }
+        }
+        assert_eq!(TimeControl::PerMove(2500 * MS).limits(40), (1250 * MS, 2500 * MS));
+    }
+
+    #[test]
+    fn clocks_are_charged_per_move() {
+        let mut control = TimeControl::SuddenDeath { remaining: 100 * MS };
+        control.charge(30 * MS);
+        assert_eq!(control, TimeControl::SuddenDeath { remaining: 70 * MS });
+        control.charge(100 * MS);
+        assert_eq!(control, TimeControl::SuddenDeath { remaining: Duration::ZERO });
+
+        let mut control = TimeControl::Increment { remaining: 100 * MS, increment: 10 * MS };
+        control.charge(30 * MS);
+        assert_eq!(control, TimeControl::Increment { remaining: 80 * MS, increment: 10 * MS });
+    }
+
+    #[test]
+    fn timed_move_replaces_the_node_budget_and_stops_in_time() {
+        let board = Board::new().flip(1 << 19).switch();
+        let mut ai = AlphaBeta5Player::with_budget(3, 1_000);
+        ai.set_time_control(Some(TimeControl::PerMove(100 * MS)));
+        let start = Instant::now();
+        let mov = ai.next(&board).unwrap();
+        // Generous slack: the hard limit is polled every 1024 nodes.
+        assert!(start.elapsed() < 600 * MS, "took {:?}", start.elapsed());
+        assert_ne!(mov & legal_moves(board.0, board.1), 0);
+        assert!(ai.last_nodes > 1_000, "the node budget still applied");
+        assert_eq!(ai.time_control(), Some(TimeControl::PerMove(100 * MS)));
+    }
+
+    #[test]
+    fn sudden_death_clock_runs_down() {
+        let mut ai = AlphaBeta5Player::new(5);
+        ai.set_time_control(Some(TimeControl::SuddenDeath { remaining: 2_000 * MS }));
+        let mut board = Board::new();
+        let mut last = 2_000 * MS;
+        for _ in 0..3 {
+            let mov = ai.next(&board).unwrap();
+            let Some(TimeControl::SuddenDeath { remaining }) = ai.time_control() else {
+                panic!("time control lost");
+            };
+            assert!(remaining < last);
+            last = remaining;
+            board = board.flip(mov).switch();
+            let reply = legal_moves(board.0, board.1);
+            board = board.flip(reply & reply.wrapping_neg()).switch();
+        }
+        assert!(last > Duration::ZERO);
+    }
+}
@@ -1,7 +1,7 @@
 //! The current strongest AI.
 use crate::reversi::bitboard;
 use crate::reversi::player::alphabeta5::AlphaBeta5Player;
-use crate::reversi::player::Player;
+use crate::reversi::player::{Player, SearchControl};
 
 /// The current best AI. Delegates to whichever concrete engine is strongest.
 pub struct BestAiPlayer(AlphaBeta5Player);
@@ -25,4 +25,11 @@ impl Player for BestAiPlayer {
     fn name(&self) -> &'static str {
         self.0.name()
     }
+    fn set_control(&mut self, control: Option<SearchControl>) {
+        self.0.set_control(control)
+    }
+
+    fn control(&self) -> Option<SearchControl> {
+        self.0.control()
+    }
 }
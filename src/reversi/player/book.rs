@@ -0,0 +1,142 @@
+use crate::reversi::bitboard::{Board, Mask};
+use crate::reversi::book::Book;
+use crate::reversi::player::{Player, SearchControl};
+use crate::reversi::rand;
+
+/// Plays from an opening book while the position is in it, then delegates to
+/// `inner`. Among the book moves, those scoring within `randomness` disks of
+/// the best are picked uniformly at random, so games vary without giving
+/// away more than that; with the default of 0 only the best-scoring moves are
+/// played. Moves seen in fewer than `min_visits` games are ignored.
+pub struct BookPlayer<P: Player> {
+    book: Book,
+    inner: P,
+    rand: rand::Xor128,
+    randomness: i32,
+    min_visits: u32,
+}
+
+impl<P: Player> BookPlayer<P> {
+    pub fn new(book: Book, inner: P, seed: u32) -> BookPlayer<P> {
+        BookPlayer {
+            book,
+            inner,
+            rand: rand::Xor128::from_seed(seed),
+            randomness: 0,
+            min_visits: 1,
+        }
+    }
+
+    /// Allows book moves scoring up to `disks` below the best one.
+    pub fn set_randomness(&mut self, disks: i32) {
+        self.randomness = disks.max(0);
+    }
+
+    pub fn set_min_visits(&mut self, visits: u32) {
+        self.min_visits = visits;
+    }
+
+    pub fn inner(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    /// The move the book suggests for `board`, if it knows the position.
+    fn book_move(&mut self, board: &Board) -> Option<Mask> {
+        let moves = self.book.moves(board, self.min_visits);
+        let best = moves.first()?.score;
+        let candidates: Vec<Mask> =
+            moves.iter().filter(|m| m.score >= best - self.randomness).map(|m| m.mov).collect();
+        Some(candidates[self.rand.next() as usize % candidates.len()])
+    }
+}
+
+impl<P: Player> Player for BookPlayer<P> {
+    fn next(&mut self, board: &Board) -> Option<Mask> {
+        self.book_move(board).or_else(|| self.inner.next(board))
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn set_control(&mut self, control: Option<SearchControl>) {
+        self.inner.set_control(control)
+    }
+
+    fn control(&self) -> Option<SearchControl> {
+        self.inner.control()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::bitboard::{legal_moves, position_to_mask};
+
+    // Counts how often it is asked for a move, and plays the first legal one.
+    struct Fallback(u32);
+
+    impl Player for Fallback {
+        fn next(&mut self, board: &Board) -> Option<Mask> {
+            self.0 += 1;
+            let legal = legal_moves(board.0, board.1);
+            (legal != 0).then(|| legal & legal.wrapping_neg())
+        }
+        fn name(&self) -> &'static str {
+            "Fallback"
+        }
+    }
+
+    fn book() -> Book {
+        // f5 d6 won for black and f5 f4 for white. All four first moves are
+        // images of f5, so they tie in the book.
+        let mut book = Book::new();
+        let f5 = Some(position_to_mask(4, 5));
+        let d6 = Some(position_to_mask(5, 3));
+        let f4 = Some(position_to_mask(3, 5));
+        book.add_game(&[f5, d6], 20, 10).unwrap();
+        book.add_game(&[f5, f4], -10, 10).unwrap();
+        book
+    }
+
+    #[test]
+    fn plays_the_best_book_move_then_delegates() {
+        let mut player = BookPlayer::new(book(), Fallback(0), 1);
+        let board = Board::new();
+        let first = player.next(&board).unwrap();
+        assert_eq!(player.inner().0, 0);
+
+        // As white, the book prefers the reply that won for white (f4's image).
+        let board = board.flip(first).switch();
+        let reply = player.next(&board).unwrap();
+        assert_eq!(player.inner().0, 0);
+        let moves = player.book.moves(&board, 1);
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0], crate::reversi::book::BookMove { mov: reply, score: 10, visits: 1 });
+
+        // Out of book.
+        let board = board.flip(reply).switch();
+        player.next(&board).unwrap();
+        assert_eq!(player.inner().0, 1);
+    }
+
+    #[test]
+    fn randomness_admits_worse_moves() {
+        let board = Board::new().flip(position_to_mask(4, 5)).switch();
+        let mut strict = BookPlayer::new(book(), Fallback(0), 3);
+        let mut loose = BookPlayer::new(book(), Fallback(0), 3);
+        loose.set_randomness(30);
+        let mut strict_moves = 0;
+        let mut loose_moves = 0;
+        for _ in 0..40 {
+            strict_moves |= strict.next(&board).unwrap();
+            loose_moves |= loose.next(&board).unwrap();
+        }
+        assert_eq!(strict_moves.count_ones(), 1);
+        assert_eq!(loose_moves.count_ones(), 2);
+
+        strict.set_min_visits(2);
+        strict.next(&board).unwrap();
+        assert_eq!(strict.inner().0, 1);
+    }
+}
@@ -0,0 +1,372 @@
+//! Static evaluation shared by the alpha-beta engines: the `Evaluator` trait the
+//! search core (`search`) scores its leaves with, and the classic Othello terms
+//! the engines' evaluations are built from.
+
+use crate::reversi::bitboard::*;
+
+// Files A (leftmost) and H (rightmost) columns. Used to stop horizontal bit
+// shifts from wrapping around row boundaries when smearing into neighbours.
+const NOT_FILE_A: Mask = 0xFEFEFEFEFEFEFEFE;
+const NOT_FILE_H: Mask = 0x7F7F7F7F7F7F7F7F;
+
+/// A static evaluation for `search::Searcher`.
+pub trait Evaluator {
+    /// Score of `board` for the side to move (`board.0`), higher is better.
+    /// `moves` are the legal moves of `board.0` and of `board.1`, at least one
+    /// of them non-empty: finished games are scored by the search itself.
+    fn evaluate(&self, board: &Board, moves: &(Mask, Mask)) -> i32;
+}
+
+/// The linear evaluation terms of `board` for black, given the legal moves of
+/// black and white: the differences in positional weight, mobility, potential
+/// mobility, frontier disks (white minus black, as fewer is better), stable
+/// disks and disks. The engines weigh them and the tuner fits the weights to
+/// them, so both read the board through this one function.
+#[inline]
+pub(crate) fn terms(board: &Board, moves: &(Mask, Mask)) -> [i32; 6] {
+    let Board(black, white) = *board;
+    let (black_moves, white_moves) = *moves;
+
+    let posdiff = positional(black) - positional(white);
+    let mobdiff = black_moves.count_ones() as i32 - white_moves.count_ones() as i32;
+
+    let (bpm, wpm) = potential_mobility(board);
+    let pmobdiff = bpm as i32 - wpm as i32;
+
+    let (bf, wf) = frontier_counts(board);
+    let frontdiff = wf as i32 - bf as i32;
+
+    let (bs, ws) = stable_full(board);
+    let stabdiff = bs as i32 - ws as i32;
+
+    let diskdiff = black.count_ones() as i32 - white.count_ones() as i32;
+
+    [posdiff, mobdiff, pmobdiff, frontdiff, stabdiff, diskdiff]
+}
+
+/// The evaluation terms of `board` for black with its legal moves worked out,
+/// in the order `terms` gives them. Exposed for fitting the weights (see
+/// `tuner`).
+pub fn features(board: &Board) -> [i32; 6] {
+    terms(board, &(legal_moves(board.0, board.1), legal_moves(board.1, board.0)))
+}
+
+/// Index of the weight set for a position with `empties` empty cells:
+/// 0 (opening) from 40 empties, 1 (midgame) from 20, 2 (endgame) below. Every
+/// phase-weighted evaluation, ProbCut and the tuner split the game this way.
+#[inline]
+pub fn phase(empties: u32) -> usize {
+    match empties {
+        40.. => 0,
+        20.. => 1,
+        _ => 2,
+    }
+}
+
+/// Linear-combination weights for the evaluation terms (one set per game phase).
+#[derive(Clone, Copy)]
+pub struct Weights {
+    /// Positional weight: the hand-tuned square table (corners good, X/C squares
+    /// bad). See `positional`.
+    pub pos: i32,
+    /// Current mobility: difference in the number of legal moves available now.
+    pub mob: i32,
+    /// Potential mobility: difference in empty cells adjacent to the opponent's
+    /// disks — squares where future moves may appear. See `potential_mobility`.
+    pub pmob: i32,
+    /// Frontier: difference in disks adjacent to an empty cell (fewer is better,
+    /// so the term is white − black). See `frontier_counts`.
+    pub front: i32,
+    /// Stability: difference in disks that can never be flipped. See `stable_full`.
+    pub stab: i32,
+    /// Raw disk count difference. Only matters near the end (kept at 0 until the
+    /// endgame phase).
+    pub disk: i32,
+}
+
+/// The three per-phase weight sets, selected by `phase`. Injectable so the
+/// tuning benches can sweep candidate weightings against a fixed opponent.
+#[derive(Clone, Copy)]
+pub struct PhaseWeights {
+    pub opening: Weights,
+    pub midgame: Weights,
+    pub endgame: Weights,
+}
+
+impl Default for PhaseWeights {
+    /// `AlphaBeta4Player`'s weights. `AlphaBeta5Player` plays the tuned
+    /// `alphabeta5::tuned_weights` instead.
+    fn default() -> Self {
+        // Carried over from AB3's tuned "more-stability" weights, plus a new
+        // potential-mobility term (`pmob`) seeded as a weaker mirror of current
+        // mobility. `pmob` still wants confirming/tuning against AB3 in the duel.
+        PhaseWeights {
+            // Opening: mobility and frontier dominate; raw disk count is irrelevant.
+            opening: Weights { pos: 100, mob: 20, pmob: 15, front: 35, stab: 40, disk: 0 },
+            // Midgame: positional table and stability lead, mobility still matters.
+            midgame: Weights { pos: 100, mob: 15, pmob: 10, front: 25, stab: 70, disk: 0 },
+            // Late: stability and disk count dominate, mobility fades.
+            endgame: Weights { pos: 80, mob: 5, pmob: 3, front: 10, stab: 100, disk: 12 },
+        }
+    }
+}
+
+impl PhaseWeights {
+    #[inline]
+    fn select(&self, empties: u32) -> &Weights {
+        match phase(empties) {
+            0 => &self.opening,
+            1 => &self.midgame,
+            _ => &self.endgame,
+        }
+    }
+}
+
+/// Enhanced evaluation: `features` weighted by game phase.
+impl Evaluator for PhaseWeights {
+    #[inline]
+    fn evaluate(&self, board: &Board, moves: &(Mask, Mask)) -> i32 {
+        let empties = 64 - (board.0 | board.1).count_ones();
+        let wt = self.select(empties);
+        let [posdiff, mobdiff, pmobdiff, frontdiff, stabdiff, diskdiff] = terms(board, moves);
+
+        wt.pos * posdiff
+            + wt.mob * mobdiff
+            + wt.pmob * pmobdiff
+            + wt.front * frontdiff
+            + wt.stab * stabdiff
+            + wt.disk * diskdiff
+    }
+}
+
+/// Positional weight of `disks` using the hand-tuned square table of the
+/// baseline player (corners good, X/C squares bad).
+#[inline]
+pub fn positional(disks: Mask) -> i32 {
+    // https://uguisu.skr.jp/othello/5-1.html
+    const ADD30: Mask = 0b_10000001_00000000_00000000_00000000_00000000_00000000_00000000_10000001;
+    const SUB01: Mask = 0b_00011000_00000000_00011000_10111101_10111101_00011000_00000000_00011000;
+    const SUB03: Mask = 0b_00000000_00111100_01000010_01000010_01000010_01000010_00111100_00000000;
+    const SUB12: Mask = 0b_01000010_10000001_00000000_00000000_00000000_00000000_10000001_01000010;
+    const SUB16: Mask = 0b_00000000_01000010_00000000_00000000_00000000_00000000_01000010_00000000;
+    let mut w = 0;
+    w += ((ADD30 & disks).count_ones() * 30) as i32;
+    w -= (SUB01 & disks).count_ones() as i32;
+    w -= ((SUB03 & disks).count_ones() * 3) as i32;
+    w -= ((SUB12 & disks).count_ones() * 12) as i32;
+    w -= ((SUB16 & disks).count_ones() * 16) as i32;
+    w
+}
+
+/// Smears `disks` into all eight neighbouring directions, guarding the
+/// horizontal shifts against wrapping across row boundaries.
+#[inline]
+pub fn neighbours(disks: Mask) -> Mask {
+    ((disks << 1) & NOT_FILE_A)
+        | ((disks >> 1) & NOT_FILE_H)
+        | (disks << 8)
+        | (disks >> 8)
+        | ((disks << 9) & NOT_FILE_A)
+        | ((disks >> 9) & NOT_FILE_H)
+        | ((disks << 7) & NOT_FILE_H)
+        | ((disks >> 7) & NOT_FILE_A)
+}
+
+/// Counts frontier disks (disks adjacent to at least one empty cell) for
+/// black and white. Frontier disks are usually a liability: they can be
+/// captured, and having many of them tends to hand the opponent mobility.
+#[inline]
+pub fn frontier_counts(board: &Board) -> (u32, u32) {
+    let Board(black, white) = *board;
+    let empty = !(black | white);
+    let neighbours = neighbours(empty);
+    ((black & neighbours).count_ones(), (white & neighbours).count_ones())
+}
+
+/// Potential mobility for black and white: the number of empty cells adjacent
+/// to the *opponent's* disks — squares where each side may gain future moves.
+/// A cheap, well-known complement to current mobility.
+#[inline]
+pub fn potential_mobility(board: &Board) -> (u32, u32) {
+    let Board(black, white) = *board;
+    let empty = !(black | white);
+    let black_pmob = (neighbours(white) & empty).count_ones();
+    let white_pmob = (neighbours(black) & empty).count_ones();
+    (black_pmob, white_pmob)
+}
+
+/// Conservative (lower-bound) count of stable disks for black and white, as
+/// `AlphaBeta2` counts them. A disk is counted as stable if it is a corner, or
+/// lies on an edge in an unbroken run of same-colored disks anchored at a
+/// corner that color owns. It ignores interior stability; see `stable_full`
+/// for the tighter count.
+#[inline]
+pub fn stable_edges(board: &Board) -> (u32, u32) {
+    let Board(black, white) = *board;
+
+    // The four edges, each as its 8 cell masks ordered from one corner to
+    // the other.
+    let mut edges: [[Mask; 8]; 4] = [[0; 8]; 4];
+    for i in 0..8u32 {
+        edges[0][i as usize] = 1 << i; // top row
+        edges[1][i as usize] = 1 << (56 + i); // bottom row
+        edges[2][i as usize] = 1 << (i * 8); // left column
+        edges[3][i as usize] = 1 << (i * 8 + 7); // right column
+    }
+
+    // Stable cells of `color` on one edge: same-colored runs growing inward
+    // from whichever ends are corners owned by `color`.
+    fn edge_stable(cells: &[Mask; 8], color: Mask) -> Mask {
+        let mut stable = 0;
+        if cells[0] & color != 0 {
+            for &c in cells.iter() {
+                if c & color != 0 {
+                    stable |= c;
+                } else {
+                    break;
+                }
+            }
+        }
+        if cells[7] & color != 0 {
+            for &c in cells.iter().rev() {
+                if c & color != 0 {
+                    stable |= c;
+                } else {
+                    break;
+                }
+            }
+        }
+        stable
+    }
+
+    // Union the per-edge stable masks so corners shared by two edges are not
+    // double-counted, then pop-count.
+    let mut sb = 0u64;
+    let mut sw = 0u64;
+    for edge in &edges {
+        sb |= edge_stable(edge, black);
+        sw |= edge_stable(edge, white);
+    }
+    (sb.count_ones(), sw.count_ones())
+}
+
+/// Flood-fill stable-disk count for black and white. A disk is stable when it
+/// can never be flipped: along each of the four axes (horizontal, vertical,
+/// and the two diagonals) it is either on the board edge for that axis, part
+/// of a completely filled line, or flanked by an already-stable same-colored
+/// disk. Seeded by the corners, iterated to a fixpoint.
+#[inline]
+pub fn stable_full(board: &Board) -> (u32, u32) {
+    let Board(black, white) = *board;
+    let empty = !(black | white);
+    const ALL: Mask = u64::MAX;
+
+    // Spread `bits` fully along one axis: `s` cells up (`<< s`, guarded by
+    // `gu`) and down (`>> s`, guarded by `gd`), 7 steps covering the board.
+    #[inline]
+    fn spread(bits: Mask, s: u32, gu: Mask, gd: Mask) -> Mask {
+        let mut r = bits;
+        let mut m = bits;
+        for _ in 0..7 {
+            m = (m << s) & gu;
+            r |= m;
+        }
+        let mut m = bits;
+        for _ in 0..7 {
+            m = (m >> s) & gd;
+            r |= m;
+        }
+        r
+    }
+
+    // Cells whose whole line (in each axis) is fully occupied = the complement
+    // of cells sharing that line with an empty.
+    let full_h = !spread(empty, 1, NOT_FILE_A, NOT_FILE_H);
+    let full_v = !spread(empty, 8, ALL, ALL);
+    let full_d = !spread(empty, 9, NOT_FILE_A, NOT_FILE_H);
+    let full_a = !spread(empty, 7, NOT_FILE_H, NOT_FILE_A);
+
+    const FILE_A: Mask = 0x0101010101010101;
+    const FILE_H: Mask = 0x8080808080808080;
+    const RANK_1: Mask = 0x00000000000000FF;
+    const RANK_8: Mask = 0xFF00000000000000;
+    const BORDER: Mask = FILE_A | FILE_H | RANK_1 | RANK_8;
+
+    // Static per-axis safety: a full line, or the board edge for that axis.
+    let sh = full_h | FILE_A | FILE_H;
+    let sv = full_v | RANK_1 | RANK_8;
+    let sd = full_d | BORDER;
+    let sa = full_a | BORDER;
+
+    #[inline]
+    fn stable_of(color: Mask, sh: Mask, sv: Mask, sd: Mask, sa: Mask) -> Mask {
+        let mut stable = 0u64;
+        loop {
+            let safe_h = sh | ((stable << 1) & NOT_FILE_A) | ((stable >> 1) & NOT_FILE_H);
+            let safe_v = sv | (stable << 8) | (stable >> 8);
+            let safe_d = sd | ((stable << 9) & NOT_FILE_A) | ((stable >> 9) & NOT_FILE_H);
+            let safe_a = sa | ((stable << 7) & NOT_FILE_H) | ((stable >> 7) & NOT_FILE_A);
+            let next = color & safe_h & safe_v & safe_d & safe_a;
+            if next == stable {
+                return stable;
+            }
+            stable = next;
+        }
+    }
+
+    (stable_of(black, sh, sv, sd, sa).count_ones(), stable_of(white, sh, sv, sd, sa).count_ones())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frontier_of_initial_board() {
+        // On the opening position the four central disks each touch an empty
+        // cell, so both sides have two frontier disks.
+        assert_eq!(frontier_counts(&Board::new()), (2, 2));
+    }
+
+    #[test]
+    fn potential_mobility_initial_board_symmetric() {
+        // The initial position is symmetric under a 180° rotation that swaps
+        // colors, so both sides must have equal (and non-zero) potential mobility.
+        let (b, w) = potential_mobility(&Board::new());
+        assert_eq!(b, w);
+        assert!(b > 0);
+    }
+
+    #[test]
+    fn stable_counts_detect_corners_only() {
+        // Black holds the four corners, nothing else -> exactly four stable
+        // disks, all black; white has none.
+        let corners = position_to_mask(0, 0)
+            | position_to_mask(0, 7)
+            | position_to_mask(7, 0)
+            | position_to_mask(7, 7);
+        let board = Board(corners, 0);
+        assert_eq!(stable_edges(&board), (4, 0));
+        assert_eq!(stable_full(&board), (4, 0));
+    }
+
+    #[test]
+    fn stable_counts_full_edge_from_corner() {
+        // A complete top edge owned by black is fully stable (8 disks); the
+        // opposite color has none.
+        let mut top = 0u64;
+        for c in 0..8 {
+            top |= position_to_mask(0, c);
+        }
+        let board = Board(top, 0);
+        assert_eq!(stable_edges(&board), (8, 0));
+        assert_eq!(stable_full(&board), (8, 0));
+    }
+
+    #[test]
+    fn stable_full_full_board_all_stable() {
+        // A completely filled board: every disk is stable (no empty to flip into).
+        let board = Board(u64::MAX, 0);
+        assert_eq!(stable_full(&board), (64, 0));
+    }
+}
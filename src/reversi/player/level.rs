@@ -0,0 +1,256 @@
+//! Adjustable-strength opponent built on `AlphaBeta5`.
+//!
+//! A level weakens the engine three ways:
+//!
+//! - a node budget and a depth cap for its search;
+//! - instead of the best move it samples one from a softmax over the scores
+//!   of every root move (`AlphaBeta5Player::analyze`), so it mostly loses a
+//!   little and occasionally a lot: a move `x` disks worse than the best is
+//!   played `exp(-x / temperature)` times as often;
+//! - with probability `error_rate` it skips the search and plays a random
+//!   legal move, the outright blunder.
+//!
+//! A level with neither softmax nor depth cap plays the engine's own move.
+//!
+//! Each of `LEVELS` beats the one below it. Measured with
+//! `cargo bench --bench league -- --duel level<n+1> level<n>`, 100 games from
+//! random 8-ply openings, half of them with each colour:
+//!
+//! | pair       | W-L-D    | avg margin |
+//! |------------|----------|------------|
+//! | 2 vs 1     | 80-18-2  | +19.4      |
+//! | 3 vs 2     | 70-25-5  | +14.4      |
+//! | 4 vs 3     | 73-23-4  | +18.7      |
+//! | 5 vs 4     | 70-30-0  | +11.0      |
+//! | 6 vs 5     | 77-22-1  | +18.8      |
+//! | 7 vs 6     | 87-12-1  | +8.8       |
+//!
+//! Re-run the pairs around a level after changing it. The CLI offers the
+//! levels as opponents; the web UI still plays the full-strength engine only.
+
+use crate::reversi::bitboard::*;
+use crate::reversi::player::alphabeta5::{AlphaBeta5Player, MoveEval, SearchLimits};
+use crate::reversi::player::{Player, SearchControl};
+use crate::reversi::rand;
+
+/// Evaluation units per disk, for putting midgame scores and solved disk
+/// differences on the same scale (see the tuner's fit of AB5's evaluation).
+const UNITS_PER_DISK: f64 = 200.0;
+
+/// Strength settings of a `LevelPlayer`.
+#[derive(Clone, Copy, Debug)]
+pub struct Level {
+    pub name: &'static str,
+    /// Node budget per move.
+    pub nodes: u64,
+    /// Deepest midgame iteration; None leaves the engine's own limit.
+    pub max_depth: Option<usize>,
+    /// Softmax temperature in disks; 0 always plays the best-scored move.
+    pub temperature: f64,
+    /// Probability of playing a random legal move without searching.
+    pub error_rate: f64,
+}
+
+/// The calibrated levels, weakest first (see the module docs). The last one
+/// weakens nothing: with `BestAiPlayer`'s node budget it plays the engine's
+/// own moves.
+pub const LEVELS: [Level; 7] = [
+    Level { name: "Level 1", nodes: 2_000, max_depth: Some(1), temperature: 6.0, error_rate: 0.3 },
+    Level {
+        name: "Level 2",
+        nodes: 10_000,
+        max_depth: Some(1),
+        temperature: 3.0,
+        error_rate: 0.15,
+    },
+    Level { name: "Level 3", nodes: 20_000, max_depth: Some(3), temperature: 3.0, error_rate: 0.1 },
+    Level {
+        name: "Level 4",
+        nodes: 50_000,
+        max_depth: Some(5),
+        temperature: 2.0,
+        error_rate: 0.05,
+    },
+    Level {
+        name: "Level 5",
+        nodes: 150_000,
+        max_depth: Some(7),
+        temperature: 1.0,
+        error_rate: 0.05,
+    },
+    Level {
+        name: "Level 6",
+        nodes: 300_000,
+        max_depth: Some(7),
+        temperature: 0.5,
+        error_rate: 0.0,
+    },
+    Level { name: "Level 7", nodes: 4_000_000, max_depth: None, temperature: 0.0, error_rate: 0.0 },
+];
+
+/// Player of a given strength, see the module docs.
+pub struct LevelPlayer {
+    engine: AlphaBeta5Player,
+    level: Level,
+    rand: rand::Xor128,
+}
+
+impl LevelPlayer {
+    /// Player of `LEVELS[level - 1]`.
+    pub fn new(seed: u32, level: usize) -> LevelPlayer {
+        assert!((1..=LEVELS.len()).contains(&level), "no level {level}");
+        Self::with_level(seed, LEVELS[level - 1])
+    }
+
+    /// Same as `new` but with custom settings.
+    pub fn with_level(seed: u32, level: Level) -> LevelPlayer {
+        LevelPlayer {
+            engine: AlphaBeta5Player::with_budget(seed, level.nodes),
+            level,
+            rand: rand::Xor128::from_seed(seed.wrapping_add(1)),
+        }
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    // Uniform in [0, 1).
+    fn uniform(&mut self) -> f64 {
+        self.rand.next() as f64 / (u32::MAX as f64 + 1.0)
+    }
+
+    // A random element of `moves` (non-empty).
+    fn random_move(&mut self, moves: Mask) -> Mask {
+        let mut m = moves;
+        for _ in 0..self.rand.next() % moves.count_ones() {
+            m &= m - 1;
+        }
+        m & m.wrapping_neg()
+    }
+
+    // Samples a root move by softmax over the engine's scores.
+    fn sample(&mut self, board: &Board) -> Mask {
+        let limits = SearchLimits {
+            nodes: self.level.nodes,
+            max_depth: self.level.max_depth.unwrap_or(usize::MAX),
+        };
+        let evals = self.engine.analyze(board, &limits);
+        if self.level.temperature <= 0.0 {
+            return evals[0].mov;
+        }
+        let best = disks(&evals[0]);
+        let weights: Vec<f64> =
+            evals.iter().map(|e| ((disks(e) - best) / self.level.temperature).exp()).collect();
+        let mut x = self.uniform() * weights.iter().sum::<f64>();
+        for (e, w) in evals.iter().zip(&weights) {
+            if x < *w {
+                return e.mov;
+            }
+            x -= w;
+        }
+        evals[0].mov
+    }
+}
+
+// A root move's score in disks.
+fn disks(e: &MoveEval) -> f64 {
+    if e.solved {
+        e.score as f64
+    } else {
+        e.score as f64 / UNITS_PER_DISK
+    }
+}
+
+impl Player for LevelPlayer {
+    fn next(&mut self, board: &Board) -> Option<Mask> {
+        let moves = legal_moves(board.0, board.1);
+        if moves == 0 {
+            return None;
+        }
+        if self.level.error_rate > 0.0 && self.uniform() < self.level.error_rate {
+            return Some(self.random_move(moves));
+        }
+        if self.level.temperature <= 0.0 && self.level.max_depth.is_none() {
+            return self.engine.next(board);
+        }
+        Some(self.sample(board))
+    }
+
+    fn name(&self) -> &'static str {
+        self.level.name
+    }
+
+    fn set_control(&mut self, control: Option<SearchControl>) {
+        self.engine.set_control(control)
+    }
+
+    fn control(&self) -> Option<SearchControl> {
+        self.engine.control()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::playout::{random_position, Stop};
+
+    #[test]
+    fn weak_levels_play_legal_moves() {
+        for level in 1..=3 {
+            let mut player = LevelPlayer::new(level as u32, level);
+            for seed in 0..8 {
+                let board = random_position(seed, Stop::Plies(4 * seed as usize));
+                let moves = legal_moves(board.0, board.1);
+                let mov = player.next(&board);
+                if moves == 0 {
+                    assert_eq!(mov, None);
+                } else {
+                    let mov = mov.unwrap();
+                    assert!(mov.count_ones() == 1 && mov & moves != 0, "level {level}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_temperature_plays_the_best_scored_move() {
+        let level = Level {
+            name: "test",
+            nodes: 20_000,
+            max_depth: Some(3),
+            temperature: 0.0,
+            error_rate: 0.0,
+        };
+        for seed in 0..4 {
+            let board = random_position(seed, Stop::Plies(20));
+            let limits = SearchLimits { nodes: level.nodes, max_depth: 3 };
+            let best = AlphaBeta5Player::with_budget(seed, level.nodes).analyze(&board, &limits)[0];
+            let mov = LevelPlayer::with_level(seed, level).next(&board);
+            assert_eq!(mov, Some(best.mov), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn high_temperature_varies_the_move() {
+        let level = Level {
+            name: "test",
+            nodes: 5_000,
+            max_depth: Some(1),
+            temperature: 100.0,
+            error_rate: 0.0,
+        };
+        let board = random_position(1, Stop::Plies(20));
+        let mut played = 0;
+        for seed in 0..16 {
+            played |= LevelPlayer::with_level(seed, level).next(&board).unwrap();
+        }
+        assert!(played.count_ones() > 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "no level 0")]
+    fn levels_start_at_one() {
+        LevelPlayer::new(1, 0);
+    }
+}
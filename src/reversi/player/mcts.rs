@@ -0,0 +1,354 @@
+//! Monte Carlo tree search (UCT), for comparing a sampling search with the
+//! alpha-beta engines.
+//!
+//! Every iteration walks down the tree taking the child with the best UCB1
+//! bound, adds one new child, plays the game out from it with uniformly random
+//! moves and backs the result (win 1, draw 1/2, loss 0) up the path. The move
+//! played is the most visited child of the root. The tree is rebuilt for every
+//! move.
+//!
+//! With `set_playout_cutoff(Some(plies))` a playout stops after that many
+//! plies and the position is scored by `AlphaBeta5Player::static_evaluation`,
+//! squashed into a win probability, instead of being played to the end.
+
+use crate::reversi::bitboard::*;
+use crate::reversi::player::alphabeta5::AlphaBeta5Player;
+use crate::reversi::player::{Player, SearchControl};
+use crate::reversi::rand;
+
+/// Iterations (playouts) per move of `new`.
+pub const DEFAULT_ITERATIONS: u32 = 20_000;
+
+// UCB1's exploration constant; sqrt(2) is the textbook value for rewards in
+// [0, 1].
+const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+// Evaluation units per logistic unit when a cut-off playout is turned into a
+// win probability. Fitting AB5's evaluation against final disk differences
+// (`tuner`) gives about 200 units per disk, so a 5-disk lead scores 73%.
+const EVAL_SCALE: f64 = 1000.0;
+
+// Parent of the root.
+const NO_PARENT: usize = usize::MAX;
+
+struct Node {
+    /// The position, side to move in `.0`.
+    board: Board,
+    /// The move that led here; None is a pass.
+    mov: Option<Mask>,
+    parent: usize,
+    children: Vec<usize>,
+    /// Legal moves not expanded yet.
+    untried: Mask,
+    /// The side to move has to pass and the pass child is not expanded yet.
+    pass_untried: bool,
+    visits: u32,
+    /// Sum of the rewards of the player who moved into this node.
+    reward: f64,
+}
+
+impl Node {
+    fn new(board: Board, mov: Option<Mask>, parent: usize) -> Node {
+        let untried = legal_moves(board.0, board.1);
+        let pass_untried = untried == 0 && legal_moves(board.1, board.0) != 0;
+        Node {
+            board,
+            mov,
+            parent,
+            children: Vec::new(),
+            untried,
+            pass_untried,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    fn expandable(&self) -> bool {
+        self.untried != 0 || self.pass_untried
+    }
+}
+
+/// Player choosing moves by UCT with random playouts.
+pub struct MctsPlayer {
+    rand: rand::Xor128,
+    iterations: u32,
+    exploration: f64,
+    cutoff: Option<u32>,
+    tree: Vec<Node>,
+    control: Option<SearchControl>,
+}
+
+impl MctsPlayer {
+    pub fn new(seed: u32) -> MctsPlayer {
+        Self::with_iterations(seed, DEFAULT_ITERATIONS)
+    }
+
+    /// Same as `new` but with an explicit number of iterations per move.
+    pub fn with_iterations(seed: u32, iterations: u32) -> MctsPlayer {
+        MctsPlayer {
+            rand: rand::Xor128::from_seed(seed),
+            iterations,
+            exploration: DEFAULT_EXPLORATION,
+            cutoff: None,
+            tree: Vec::new(),
+            control: None,
+        }
+    }
+
+    /// Ends playouts after `plies` moves, scoring the position with AB5's
+    /// static evaluation. None (the default) plays every playout to the end.
+    pub fn set_playout_cutoff(&mut self, plies: Option<u32>) {
+        self.cutoff = plies;
+    }
+
+    /// Sets UCB1's exploration constant (default sqrt(2)).
+    pub fn set_exploration(&mut self, exploration: f64) {
+        self.exploration = exploration;
+    }
+
+    fn iterate(&mut self) {
+        // Selection.
+        let mut node = 0;
+        while !self.tree[node].expandable() && !self.tree[node].children.is_empty() {
+            node = self.select(node);
+        }
+        // Expansion.
+        if self.tree[node].expandable() {
+            node = self.expand(node);
+        }
+        // Simulation, for the side to move at `node`.
+        let board = self.tree[node].board.clone();
+        let value = self.playout(&board);
+        // Backpropagation: rewards alternate between the two players.
+        let mut reward = 1.0 - value;
+        loop {
+            let n = &mut self.tree[node];
+            n.visits += 1;
+            n.reward += reward;
+            if n.parent == NO_PARENT {
+                break;
+            }
+            node = n.parent;
+            reward = 1.0 - reward;
+        }
+    }
+
+    // The child of `node` with the highest UCB1 bound.
+    fn select(&self, node: usize) -> usize {
+        let log_visits = (self.tree[node].visits as f64).ln();
+        let ucb = |child: usize| {
+            let n = &self.tree[child];
+            let visits = n.visits as f64;
+            n.reward / visits + self.exploration * (log_visits / visits).sqrt()
+        };
+        let children = &self.tree[node].children;
+        let mut best = children[0];
+        let mut best_ucb = ucb(best);
+        for &child in &children[1..] {
+            let u = ucb(child);
+            if u > best_ucb {
+                best = child;
+                best_ucb = u;
+            }
+        }
+        best
+    }
+
+    // Adds a random unexpanded child of `node` and returns it.
+    fn expand(&mut self, node: usize) -> usize {
+        let parent = &mut self.tree[node];
+        let (board, mov) = if parent.untried != 0 {
+            let mov = rand_move(&mut self.rand, parent.untried);
+            parent.untried &= !mov;
+            (parent.board.flip(mov).switch(), Some(mov))
+        } else {
+            parent.pass_untried = false;
+            (parent.board.switch(), None)
+        };
+        let child = self.tree.len();
+        self.tree[node].children.push(child);
+        self.tree.push(Node::new(board, mov, node));
+        child
+    }
+
+    // Plays random moves from `board` and returns the reward of its side to
+    // move: the result if the game ends, the evaluation at the cut-off else.
+    fn playout(&mut self, board: &Board) -> f64 {
+        let (mut me, mut opp) = (board.0, board.1);
+        let mut swapped = false;
+        let mut passed = false;
+        let mut plies = 0;
+        let value = loop {
+            if self.cutoff == Some(plies) {
+                let eval = AlphaBeta5Player::static_evaluation(&Board(me, opp));
+                break 1.0 / (1.0 + (-eval as f64 / EVAL_SCALE).exp());
+            }
+            let moves = legal_moves(me, opp);
+            if moves == 0 {
+                if passed {
+                    break match me.count_ones().cmp(&opp.count_ones()) {
+                        std::cmp::Ordering::Greater => 1.0,
+                        std::cmp::Ordering::Less => 0.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                    };
+                }
+                passed = true;
+            } else {
+                passed = false;
+                let mov = rand_move(&mut self.rand, moves);
+                let flips = flip_disks(me, opp, mov);
+                me |= mov | flips;
+                opp ^= flips;
+                plies += 1;
+            }
+            std::mem::swap(&mut me, &mut opp);
+            swapped = !swapped;
+        };
+        if swapped {
+            1.0 - value
+        } else {
+            value
+        }
+    }
+
+    fn stopped(&self) -> bool {
+        self.control.as_ref().is_some_and(|c| c.is_stopped())
+    }
+}
+
+impl Player for MctsPlayer {
+    fn next(&mut self, board: &Board) -> Option<Mask> {
+        let moves = legal_moves(board.0, board.1);
+        if moves == 0 {
+            return None;
+        }
+        if moves.count_ones() == 1 {
+            return Some(moves);
+        }
+
+        self.tree.clear();
+        self.tree.push(Node::new(board.clone(), None, NO_PARENT));
+        for _ in 0..self.iterations {
+            if self.stopped() {
+                break;
+            }
+            self.iterate();
+        }
+        let best = self.tree[0].children.iter().max_by_key(|&&child| self.tree[child].visits);
+        // Stopped before the first iteration: any legal move.
+        Some(best.and_then(|&child| self.tree[child].mov).unwrap_or(moves & moves.wrapping_neg()))
+    }
+
+    fn name(&self) -> &'static str {
+        "MCTS"
+    }
+
+    fn set_control(&mut self, control: Option<SearchControl>) {
+        self.control = control;
+    }
+
+    fn control(&self) -> Option<SearchControl> {
+        self.control.clone()
+    }
+}
+
+// A uniformly random move out of `moves` (non-empty).
+fn rand_move(rand: &mut rand::Xor128, moves: Mask) -> Mask {
+    let mut m = moves;
+    for _ in 0..rand.next() % moves.count_ones() {
+        m &= m - 1;
+    }
+    m & m.wrapping_neg()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::gm::{GameManager, Winner};
+    use crate::reversi::player::alphabeta5::Outcome;
+    use crate::reversi::player::random::RandomPlayer;
+    use crate::reversi::playout::{random_position, Stop};
+
+    const GAMES: u32 = 10;
+    const MIN_WINS: u32 = 9;
+    const TEST_ITERATIONS: u32 = 1_000;
+
+    fn wins_against_random(mcts: impl Fn(u32) -> MctsPlayer, mcts_is_black: bool) -> u32 {
+        let mut wins = 0;
+        for seed in 0..GAMES {
+            let mcts = Box::new(mcts(seed));
+            let rand = Box::new(RandomPlayer::new(seed.wrapping_add(1_000_000)));
+            let (expected, mut gm) = if mcts_is_black {
+                (Winner::Black, GameManager::new(mcts, rand))
+            } else {
+                (Winner::White, GameManager::new(rand, mcts))
+            };
+            if gm.playout().winner == expected {
+                wins += 1;
+            }
+        }
+        wins
+    }
+
+    #[test]
+    fn beats_random_on_both_colours() {
+        for mcts_is_black in [true, false] {
+            let wins = wins_against_random(
+                |seed| MctsPlayer::with_iterations(seed, TEST_ITERATIONS),
+                mcts_is_black,
+            );
+            assert!(wins >= MIN_WINS, "MCTS (black: {mcts_is_black}) won only {wins}/{GAMES}");
+        }
+    }
+
+    #[test]
+    fn beats_random_with_evaluation_cutoff() {
+        let mcts = |seed| {
+            let mut player = MctsPlayer::with_iterations(seed, TEST_ITERATIONS);
+            player.set_playout_cutoff(Some(4));
+            player
+        };
+        let wins = wins_against_random(mcts, true);
+        assert!(wins >= MIN_WINS, "MCTS with cut-off won only {wins}/{GAMES}");
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_moves() {
+        let moves = |seed| {
+            let mut player = MctsPlayer::with_iterations(seed, 200);
+            let mut board = Board::new();
+            let mut moves = Vec::new();
+            for _ in 0..10 {
+                let mov = player.next(&board).unwrap();
+                moves.push(mov);
+                board = board.flip(mov).switch();
+            }
+            moves
+        };
+        assert_eq!(moves(7), moves(7));
+    }
+
+    #[test]
+    fn finds_the_best_outcome_near_the_end() {
+        // Positions with 4 empty cells from random games: the tree covers the
+        // whole game, so the chosen move must reach the best outcome there is.
+        let boards = (3..)
+            .map(|seed| random_position(seed, Stop::Empties(4)))
+            .filter(|board| legal_moves(board.0, board.1).count_ones() >= 2);
+        for board in boards.take(10) {
+            let moves = legal_moves(board.0, board.1);
+            let mut solver = AlphaBeta5Player::new(1);
+            let mut outcome = |mov: Mask| {
+                let child = board.flip(mov).switch();
+                match solver.solve_wld(&child).unwrap() {
+                    Outcome::Win => Outcome::Loss,
+                    Outcome::Draw => Outcome::Draw,
+                    Outcome::Loss => Outcome::Win,
+                }
+            };
+            let best = (0..64).map(|i| 1 << i).filter(|m| moves & m != 0).map(&mut outcome).max();
+            let mov = MctsPlayer::with_iterations(1, 2_000).next(&board).unwrap();
+            assert_eq!(Some(outcome(mov)), best, "{board}");
+        }
+    }
+}
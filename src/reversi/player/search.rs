@@ -0,0 +1,721 @@
+//! The alpha-beta search core behind `AlphaBeta` through `AlphaBeta4`.
+//!
+//! Those engines grew one copy at a time, each adding a search technique to
+//! the last; here the techniques are switches of a `SearchConfig`, and the
+//! evaluation is an `Evaluator`, so every historical engine is a configuration
+//! plus an evaluator of the one `Searcher`:
+//!
+//! | engine   | depth | endgame      | PVS | iterative deepening |
+//! |----------|-------|--------------|-----|---------------------|
+//! | `ab`     | 7     | WLD at 10    |     |                     |
+//! | `ab2`    | 7     | WLD at 10    |     |                     |
+//! | `ab3`    | 9     | WLD at 12    | yes |                     |
+//! | `ab4`    | 9     | exact at 16  | yes | yes                 |
+//!
+//! Killer moves, the history heuristic and aspiration windows, which only
+//! `AlphaBeta5` uses, can be switched on too.
+//!
+//! `AlphaBeta5` does not run on `Searcher` and is out of scope here: its node
+//! budget, fixed-size shared tables, parallel search and ProbCut have no
+//! counterpart in this core. It shares `Bound`, the table entries and the
+//! evaluation (`eval::PhaseWeights`) with the engines above.
+
+use crate::reversi::bitboard::*;
+use crate::reversi::hash::FxBuildHasher;
+use crate::reversi::player::eval::Evaluator;
+use crate::reversi::player::Player;
+use crate::reversi::rand;
+use crate::reversi::{H, W};
+use std::cmp::{max, min};
+use std::collections::HashMap;
+
+/// A enough large evaluate value.
+pub const INF: i32 = 100_000_000;
+
+/// The four corner cells, tried first during move ordering.
+pub const CORNERS: Mask =
+    0b_10000001_00000000_00000000_00000000_00000000_00000000_00000000_10000001;
+
+// Enough ply slots for killer indexing (search never recurses deeper than this).
+const MAX_PLY: usize = 64;
+
+/// Kind of value stored in a transposition table entry (or reported by
+/// `AlphaBeta5Player::analyze`), w.r.t. the search window it was produced with.
+/// - `Exact`: the value is the true score at that depth.
+/// - `Lower`: the search failed high, so the true score is >= value.
+/// - `Upper`: the search failed low, so the true score is <= value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// Transposition table entry for the heuristic depth-limited search.
+/// `depth` is the remaining search depth this value was computed with; a cached
+/// value may only be reused for a lookup that needs an equal-or-shallower search.
+#[derive(Clone, Copy)]
+pub(crate) struct SearchEntry {
+    pub depth: u8,
+    pub value: i32,
+    pub bound: Bound,
+    pub best_move: Mask,
+}
+
+/// Transposition table entry for the exact endgame solver. No depth is needed:
+/// the solver always searches to the end of the game, so the value is fully
+/// accurate for the whole subtree regardless of when it is looked up.
+#[derive(Clone, Copy)]
+pub(crate) struct SolveEntry {
+    pub value: i32,
+    pub bound: Bound,
+    pub best_move: Mask,
+}
+
+// Boards are keyed by their raw (black, white) bitmasks. The board passed to the
+// search always has the side-to-move as `.0`, so this pair fully identifies the
+// position (whose turn it is included).
+type SearchTt = HashMap<(Mask, Mask), SearchEntry, FxBuildHasher>;
+type SolveTt = HashMap<(Mask, Mask), SolveEntry, FxBuildHasher>;
+
+/// What the endgame solver maximises.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Endgame {
+    /// Only the win/loss/draw sign of the final disk difference.
+    Wld,
+    /// The final disk difference itself, playing for the widest margin.
+    Exact,
+}
+
+/// Search techniques of a `Searcher`, see the module docs.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchConfig {
+    /// Depth of the midgame search below each root move. Keep it odd for the
+    /// engines' evaluations, which have a strong even/odd (tempo) bias.
+    pub depth: usize,
+    /// Empty cells from which the endgame is solved instead of searched.
+    pub endgame_empties: u32,
+    pub endgame: Endgame,
+    /// Principal-variation search (NegaScout) below the root: null-window
+    /// probes of every move but the first, re-searched on a fail-high.
+    pub pvs: bool,
+    /// Searches the odd depths up to `depth` in turn, sharing the table and
+    /// re-ordering the root moves best-first after each iteration.
+    pub iterative_deepening: bool,
+    /// Two killer moves per ply, tried right after corners.
+    pub killers: bool,
+    /// Per-square history of cutoffs as a move-ordering tiebreak.
+    pub history: bool,
+    /// Half-width of the root window around the previous iteration's score
+    /// from depth 5 on, re-searched full width on a fail. Needs
+    /// `iterative_deepening`.
+    pub aspiration: Option<i32>,
+}
+
+/// Alpha-beta search over a transposition table, configured by `SearchConfig`
+/// and scoring leaves with `E`.
+pub struct Searcher<E> {
+    name: &'static str,
+    config: SearchConfig,
+    evaluator: E,
+    rand: rand::Xor128,
+    search_tt: SearchTt,
+    solve_tt: SolveTt,
+    killers: [[Mask; 2]; MAX_PLY],
+    history: [i64; 64],
+}
+
+impl<E: Evaluator> Searcher<E> {
+    /// A searcher playing as `name`; `seed` breaks ties between root moves.
+    pub fn new(seed: u32, name: &'static str, config: SearchConfig, evaluator: E) -> Searcher<E> {
+        debug_assert!(config.aspiration.is_none() || config.iterative_deepening);
+        Searcher {
+            name,
+            config,
+            evaluator,
+            rand: rand::Xor128::from_seed(seed),
+            search_tt: SearchTt::default(),
+            solve_tt: SolveTt::default(),
+            killers: [[0; 2]; MAX_PLY],
+            history: [0; 64],
+        }
+    }
+
+    pub fn config(&self) -> &SearchConfig {
+        &self.config
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    /// Orders the moves in `moves_mask` from most to least promising so that
+    /// alpha-beta cuts off as early as possible. Good ordering is the single most
+    /// important factor for pruning efficiency.
+    ///
+    /// Priority, highest first:
+    ///  1. `tt_move` (the best move remembered for this position, if any),
+    ///  2. moves that capture a corner,
+    ///  3. killer moves at `ply`, if enabled,
+    ///  4. moves that leave the opponent with fewer replies (low mobility),
+    ///     ties broken by the history score, if enabled.
+    ///
+    /// The solver passes no `ply`: it uses neither killers nor history.
+    fn ordered_moves(
+        &self,
+        board: &Board,
+        moves_mask: Mask,
+        parts: &[(Mask, Mask); 4],
+        tt_move: Mask,
+        ply: Option<usize>,
+    ) -> Vec<Mask> {
+        let killers = match ply {
+            Some(ply) if self.config.killers => self.killers[ply],
+            _ => [0; 2],
+        };
+        let history = ply.is_some() && self.config.history;
+        let mut scored: Vec<(i32, Mask)> = (0..H * W)
+            .map(|i| 1u64 << i)
+            .filter(|&mov| mov & moves_mask == mov)
+            .map(|mov| {
+                let score = if mov == tt_move {
+                    // Always try the transposition-table move first.
+                    i32::MAX
+                } else {
+                    // Look one ply ahead and prefer moves that give the opponent
+                    // fewer legal replies (mobility restriction).
+                    let child = board.flip_with_hints(mov, parts).switch();
+                    let (opp_moves, _) = child.get_valid_mask();
+                    let mut s = -(opp_moves.count_ones() as i32) * 1000;
+                    if mov & CORNERS != 0 {
+                        s += 1_000_000;
+                    }
+                    if mov == killers[0] {
+                        s += 500_000;
+                    } else if mov == killers[1] {
+                        s += 400_000;
+                    }
+                    if history {
+                        // Below one mobility step (1000).
+                        s += min(self.history[mov.trailing_zeros() as usize], 900) as i32;
+                    }
+                    s
+                };
+                (score, mov)
+            })
+            .collect();
+        scored.sort_by_key(|&(s, _)| std::cmp::Reverse(s));
+        scored.into_iter().map(|(_, mov)| mov).collect()
+    }
+
+    /// Records a beta cutoff of `mov` at `ply` for the killers and history.
+    /// Corners are already ordered first, so they are kept out of the killer
+    /// slots.
+    fn record_cutoff(&mut self, mov: Mask, ply: usize, depth: usize) {
+        if self.config.killers && mov & CORNERS == 0 && mov != self.killers[ply][0] {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = mov;
+        }
+        if self.config.history {
+            self.history[mov.trailing_zeros() as usize] += depth as i64;
+        }
+    }
+
+    /// Negamax alpha-beta to `depth`, with principal-variation search if
+    /// configured: the first (best-ordered) move is searched with the full
+    /// window; the rest are probed with a null window and only re-searched
+    /// when they surprise us by beating alpha.
+    fn search(
+        &mut self,
+        board: &Board,
+        alpha: i32,
+        beta: i32,
+        depth: usize,
+        ply: usize,
+        passed: bool,
+    ) -> i32 {
+        debug_assert!(alpha <= beta);
+        let (black_moves, parts) = board.get_valid_mask();
+        if depth == 0 || (black_moves == 0 && passed) {
+            let white_moves = legal_moves(board.1, board.0);
+            if black_moves == 0 && white_moves == 0 {
+                return final_score(board);
+            }
+            return self.evaluator.evaluate(board, &(black_moves, white_moves));
+        }
+        if black_moves == 0 {
+            // No valid moves, pass.
+            return -self.search(&board.switch(), -beta, -alpha, depth, ply, true);
+        }
+
+        let key = (board.0, board.1);
+        let mut alpha = alpha;
+        let mut beta = beta;
+        let orig_alpha = alpha;
+
+        // Probe the transposition table. A cached value computed at an equal-or-deeper
+        // search can shrink the window (or return immediately). Its best move, if any,
+        // is used to order the search even when the value itself is not usable.
+        let mut tt_move = 0;
+        if let Some(&e) = self.search_tt.get(&key) {
+            if e.depth as usize >= depth {
+                match e.bound {
+                    Bound::Exact => return e.value,
+                    Bound::Lower => alpha = max(alpha, e.value),
+                    Bound::Upper => beta = min(beta, e.value),
+                }
+                if alpha >= beta {
+                    return e.value;
+                }
+            }
+            tt_move = e.best_move;
+        }
+
+        let ply = ply.min(MAX_PLY - 1);
+        let moves = self.ordered_moves(board, black_moves, &parts, tt_move, Some(ply));
+        let mut best = -INF;
+        let mut best_move = moves[0];
+        for (i, &mov) in moves.iter().enumerate() {
+            let child = board.flip_with_hints(mov, &parts).switch();
+            let score = if i == 0 || !self.config.pvs {
+                -self.search(&child, -beta, -alpha, depth - 1, ply + 1, false)
+            } else {
+                // Null-window probe; re-search on a fail-high inside the window.
+                let s = -self.search(&child, -alpha - 1, -alpha, depth - 1, ply + 1, false);
+                if s > alpha && s < beta {
+                    -self.search(&child, -beta, -alpha, depth - 1, ply + 1, false)
+                } else {
+                    s
+                }
+            };
+            if score > best {
+                best = score;
+                best_move = mov;
+            }
+            alpha = max(alpha, score);
+            if alpha >= beta {
+                self.record_cutoff(mov, ply, depth);
+                break;
+            }
+        }
+
+        // Record what we learned so sibling and future searches can reuse it.
+        let bound = bound_of(best, orig_alpha, beta);
+        self.search_tt
+            .insert(key, SearchEntry { depth: depth as u8, value: best, bound, best_move });
+        best
+    }
+
+    /// Endgame solver. Searches to the end of the game (no depth limit) and
+    /// returns the final disk difference from the current player's perspective,
+    /// or only its sign (+1 win, 0 draw, -1 loss) for `Endgame::Wld`.
+    fn solve(&mut self, board: &Board, alpha: i32, beta: i32, passed: bool) -> i32 {
+        debug_assert!(alpha <= beta);
+        let (my_moves, parts) = board.get_valid_mask();
+        if my_moves == 0 {
+            if passed {
+                // Both players passed -> game over. Score by raw disk counts,
+                // matching evaluate() / GameManager::finalize().
+                let (me, opp) = board.count();
+                let diff = me as i32 - opp as i32;
+                return match self.config.endgame {
+                    Endgame::Wld => diff.signum(),
+                    Endgame::Exact => diff,
+                };
+            }
+            // No valid moves, pass.
+            return -self.solve(&board.switch(), -beta, -alpha, true);
+        }
+
+        let key = (board.0, board.1);
+        let mut alpha = alpha;
+        let mut beta = beta;
+        let orig_alpha = alpha;
+
+        // Probe the endgame table. Since every entry is solved fully to the end,
+        // the cached value is valid regardless of remaining depth; only the bound
+        // (relative to the window it was produced with) has to be respected.
+        let mut tt_move = 0;
+        if let Some(&e) = self.solve_tt.get(&key) {
+            match e.bound {
+                Bound::Exact => return e.value,
+                Bound::Lower => alpha = max(alpha, e.value),
+                Bound::Upper => beta = min(beta, e.value),
+            }
+            if alpha >= beta {
+                return e.value;
+            }
+            tt_move = e.best_move;
+        }
+
+        let moves = self.ordered_moves(board, my_moves, &parts, tt_move, None);
+        let mut best = -INF;
+        let mut best_move = moves[0];
+        for (i, &mov) in moves.iter().enumerate() {
+            let child = board.flip_with_hints(mov, &parts).switch();
+            let score = if i == 0 || !self.config.pvs {
+                -self.solve(&child, -beta, -alpha, false)
+            } else {
+                let s = -self.solve(&child, -alpha - 1, -alpha, false);
+                if s > alpha && s < beta {
+                    -self.solve(&child, -beta, -alpha, false)
+                } else {
+                    s
+                }
+            };
+            if score > best {
+                best = score;
+                best_move = mov;
+            }
+            alpha = max(alpha, score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = bound_of(best, orig_alpha, beta);
+        self.solve_tt.insert(key, SolveEntry { value: best, bound, best_move });
+        best
+    }
+
+    /// Scores the root `moves` in order with window `[alpha0, beta]`, by the
+    /// solver if `depth` is None, narrowing alpha with the best score so far
+    /// (beta stays put: there is no upper bound at the root). Returns the best
+    /// score and the moves scored best-first; ties keep their order, so the
+    /// first of the best moves comes first. On a fail-high of a narrow window
+    /// the rest of the moves are left out.
+    fn root(
+        &mut self,
+        board: &Board,
+        parts: &[(Mask, Mask); 4],
+        moves: &[Mask],
+        depth: Option<usize>,
+        alpha0: i32,
+        beta: i32,
+    ) -> (i32, Vec<Mask>) {
+        let mut alpha = alpha0;
+        let mut best = -INF;
+        let mut scored: Vec<(i32, Mask)> = Vec::with_capacity(moves.len());
+        for &mov in moves.iter() {
+            let child = board.flip_with_hints(mov, parts).switch();
+            let score = match depth {
+                Some(depth) => -self.search(&child, -beta, -alpha, depth, 0, false),
+                None => -self.solve(&child, -beta, -alpha, false),
+            };
+            scored.push((score, mov));
+            best = max(best, score);
+            alpha = max(alpha, score);
+            if alpha >= beta && beta < INF {
+                break;
+            }
+        }
+        scored.sort_by_key(|&(s, _)| std::cmp::Reverse(s));
+        (best, scored.into_iter().map(|(_, mov)| mov).collect())
+    }
+
+    /// The move to play on `board` (side to move in `.0`), None if it must pass.
+    pub fn best_move(&mut self, board: &Board) -> Option<Mask> {
+        let (black_moves, parts) = board.get_valid_mask();
+        if black_moves == 0 {
+            return None;
+        }
+
+        // Enumerate all first moves and shuffle them so that, among moves that
+        // tie for the best score, the first one encountered (and thus chosen) is
+        // picked uniformly at random.
+        let mut moves = (0..H * W)
+            .map(|i| 1u64 << i)
+            .filter(|&mov| mov & black_moves == mov)
+            .collect::<Vec<_>>();
+        let n = moves.len();
+        // Do Fisher-Yates algorithm.
+        for i in 0..n - 1 {
+            moves.swap(i, i + self.rand.next() as usize % (n - i));
+        }
+
+        // Fresh tables and move-ordering state for this decision. Entries are
+        // keyed by the exact board, so they stay valid for the whole move loop
+        // (sibling first moves share many transposed positions).
+        self.search_tt.clear();
+        self.solve_tt.clear();
+        self.killers = [[0; 2]; MAX_PLY];
+        self.history = [0; 64];
+
+        let (black, white) = board.count();
+        let empties = (H * W) as u32 - black - white;
+        if empties <= self.config.endgame_empties {
+            let (_, ordered) = self.root(board, &parts, &moves, None, -INF, INF);
+            return Some(ordered[0]);
+        }
+        if !self.config.iterative_deepening {
+            let (_, ordered) = self.root(board, &parts, &moves, Some(self.config.depth), -INF, INF);
+            return Some(ordered[0]);
+        }
+
+        // Iterative deepening over odd depths, sharing the table and re-ordering
+        // the root moves best-first after each iteration, so the previous best
+        // move sets a strong alpha before the weaker moves are tried.
+        let mut score = 0;
+        let mut depth = 1;
+        while depth <= self.config.depth {
+            let (mut best, mut ordered) = (-INF, Vec::new());
+            if let Some(delta) = self.config.aspiration.filter(|_| depth >= 5) {
+                let (alpha, beta) = (score - delta, score + delta);
+                (best, ordered) = self.root(board, &parts, &moves, Some(depth), alpha, beta);
+                if best <= alpha || best >= beta {
+                    ordered.clear();
+                }
+            }
+            if ordered.is_empty() {
+                (best, ordered) = self.root(board, &parts, &moves, Some(depth), -INF, INF);
+            }
+            score = best;
+            moves = ordered;
+            depth += 2;
+        }
+        Some(moves[0])
+    }
+}
+
+impl<E: Evaluator> Player for Searcher<E> {
+    fn next(&mut self, board: &Board) -> Option<Mask> {
+        self.best_move(board)
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Score of a finished game for the side to move: `INF` for a win, `-INF` for
+/// a loss, 0 for a draw.
+#[inline]
+pub fn final_score(board: &Board) -> i32 {
+    let (me, opp) = (board.0.count_ones(), board.1.count_ones());
+    match me.cmp(&opp) {
+        std::cmp::Ordering::Greater => INF,
+        std::cmp::Ordering::Less => -INF,
+        std::cmp::Ordering::Equal => 0,
+    }
+}
+
+/// The bound a fail-soft search result `best` gives under the window
+/// `(alpha, beta)`.
+#[inline]
+pub(crate) fn bound_of(best: i32, alpha: i32, beta: i32) -> Bound {
+    if best <= alpha {
+        Bound::Upper
+    } else if best >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::gm::{GameManager, Winner};
+    use crate::reversi::player::alphabeta::AlphaBetaSearchPlayer;
+    use crate::reversi::player::alphabeta2::AlphaBeta2Player;
+    use crate::reversi::player::alphabeta3::AlphaBeta3Player;
+    use crate::reversi::player::alphabeta4::{self, AlphaBeta4Player};
+    use crate::reversi::player::alphabeta42::AlphaBeta42Player;
+    use crate::reversi::player::random::RandomPlayer;
+    use crate::reversi::playout::{random_position, Stop};
+
+    #[test]
+    fn historical_configurations_play_the_old_engines_moves() {
+        // (seed, plies, board with X to move, squares chosen by AB, AB2, AB3,
+        // AB4 and AB42 with `new(seed)`), recorded from the engines' own
+        // searches before they became configurations of `Searcher`: openings,
+        // midgames and endgames inside the exact-solve horizons.
+        let cases: [(u32, usize, &str, [u32; 5]); 14] = [
+            (
+                1,
+                12,
+                "------------X--O---X--O--OOOXO-----XOO----XXXO------------------",
+                [38, 38, 38, 38, 38],
+            ),
+            (
+                7,
+                20,
+                "-----OX----OOOX----OXOXO---OX-X---XOXX---XOXXX-------O----------",
+                [26, 26, 26, 26, 26],
+            ),
+            (
+                8,
+                26,
+                "-----O---X--OO---XXXXX--XXOXX---OOOOX---O-OOO----XOOXXX-X-------",
+                [59, 59, 59, 59, 59],
+            ),
+            (
+                9,
+                30,
+                "-O-O--X--OXXXXX--OOOOOXX--OOOOXO--XOOX---X---X-----OOOO----X-X--",
+                [42, 42, 42, 42, 42],
+            ),
+            (
+                10,
+                34,
+                "OXXX--O--O-O-O---OOOOO-----OOO--O--OOOXX-OXOXOX-XXXXOO-O--X-XO--",
+                [40, 40, 59, 59, 59],
+            ),
+            (
+                3,
+                36,
+                "-X-O-OX-XXOXOXX--OXOXOO-O-OXX-O--OOXOOOO--OXOO---XOOXX----O--X--",
+                [2, 23, 2, 2, 2],
+            ),
+            (
+                11,
+                40,
+                "---OOOX----OOXXXOOOOXOX--OXXXXOX-XXXOOOO-XXXXOX---X-OOO-----XOOO",
+                [59, 59, 23, 23, 23],
+            ),
+            (
+                12,
+                44,
+                "-XXXXO--XXXXOOO-XXXXXOOXXXXXOOO-XXOXXOO--XOXXXOO-OXX--O-OX----O-",
+                [7, 7, 6, 6, 6],
+            ),
+            (
+                4,
+                47,
+                "--XXXX-OXXXXXXXOX-OXOXXOXOXOXXX---OXOXXXOOOOOOOO-XOOOOO----OOO-X",
+                [17, 17, 17, 17, 17],
+            ),
+            (
+                13,
+                48,
+                "OX-OOOOXOOXXOOOXOOXOOXOXXXOOXOOOXXXOOXOX-XXOO-X--XO-XO-X-O---XO-",
+                [60, 60, 60, 47, 47],
+            ),
+            (
+                5,
+                50,
+                "---XXXOO---XXOXO-O-XOX-OXOOXOOXX-OXXOOXXOXXXOOXXXXOOXOOXXXXXXXXX",
+                [22, 22, 22, 32, 32],
+            ),
+            (
+                14,
+                52,
+                "OOOO-XXX-OOOXXXXOOOXOXXX--XOXOXX-OXXOOOX-XXXXOOX-OOXXXXX-OOOXXXX",
+                [32, 32, 32, 48, 48],
+            ),
+            (
+                6,
+                53,
+                "OOOOO---XOOOOOO-XXXXXOXXXXOXOOXX-OXOOOXXOXOXOOXX-OXOXXO-OOOOOOOO",
+                [32, 32, 32, 55, 55],
+            ),
+            (
+                15,
+                56,
+                "XXXXO---X-XXOXXXXXXOOXXXXXXXOOXXXXOXOOXXXOXXOXOXXXOOOOXXXXXXOXXX",
+                [5, 5, 5, 5, 5],
+            ),
+        ];
+        for (seed, plies, board, expected) in cases {
+            let board: Board = board.parse().unwrap();
+            assert_eq!(random_position(seed, Stop::Plies(plies)), board);
+            let mut engines: [Box<dyn Player>; 5] = [
+                Box::new(AlphaBetaSearchPlayer::new(seed)),
+                Box::new(AlphaBeta2Player::new(seed)),
+                Box::new(AlphaBeta3Player::new(seed)),
+                Box::new(AlphaBeta4Player::new(seed)),
+                Box::new(AlphaBeta42Player::new(seed)),
+            ];
+            for (engine, square) in engines.iter_mut().zip(expected) {
+                let mov = engine.next(&board).map(|m| m.trailing_zeros());
+                assert_eq!(mov, Some(square), "{} at seed {seed}", engine.name());
+            }
+        }
+    }
+
+    // Plain negamax to the end of the game: the final disk difference.
+    fn negamax(board: &Board, passed: bool) -> i32 {
+        let moves = legal_moves(board.0, board.1);
+        if moves == 0 {
+            if passed {
+                let (me, opp) = board.count();
+                return me as i32 - opp as i32;
+            }
+            return -negamax(&board.switch(), true);
+        }
+        (0..64)
+            .map(|i| 1u64 << i)
+            .filter(|m| moves & m != 0)
+            .map(|m| -negamax(&board.flip(m).switch(), false))
+            .max()
+            .unwrap()
+    }
+
+    fn searcher(config: SearchConfig) -> Searcher<alphabeta4::PhaseWeights> {
+        Searcher::new(1, "test", config, alphabeta4::PhaseWeights::default())
+    }
+
+    const PLAIN: SearchConfig = SearchConfig {
+        depth: 5,
+        endgame_empties: 8,
+        endgame: Endgame::Exact,
+        pvs: false,
+        iterative_deepening: false,
+        killers: false,
+        history: false,
+        aspiration: None,
+    };
+
+    #[test]
+    fn solver_matches_negamax() {
+        for seed in 0..8 {
+            let board = random_position(seed, Stop::Plies(52));
+            let expected = negamax(&board, false);
+            for pvs in [false, true] {
+                let exact = searcher(SearchConfig { pvs, ..PLAIN }).solve(&board, -INF, INF, false);
+                assert_eq!(exact, expected, "seed {seed}");
+                let wld = SearchConfig { pvs, endgame: Endgame::Wld, ..PLAIN };
+                let sign = searcher(wld).solve(&board, -INF, INF, false);
+                assert_eq!(sign, expected.signum(), "seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn move_ordering_switches_keep_the_search_value() {
+        // PVS, killers and history only reorder and narrow the search, so the
+        // root value at a fixed depth must not change.
+        let ordered = SearchConfig { pvs: true, killers: true, history: true, ..PLAIN };
+        for seed in 0..6 {
+            let board = random_position(seed, Stop::Plies(10 + 4 * seed as usize));
+            let (moves, parts) = board.get_valid_mask();
+            let moves: Vec<Mask> = (0..64).map(|i| 1u64 << i).filter(|m| moves & m != 0).collect();
+            let (plain, _) = searcher(PLAIN).root(&board, &parts, &moves, Some(4), -INF, INF);
+            let (value, _) = searcher(ordered).root(&board, &parts, &moves, Some(4), -INF, INF);
+            assert_eq!(value, plain, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn full_configuration_beats_random() {
+        // Everything switched on, aspiration windows included.
+        let config = SearchConfig {
+            pvs: true,
+            iterative_deepening: true,
+            killers: true,
+            history: true,
+            aspiration: Some(200),
+            ..PLAIN
+        };
+        for seed in 0..6 {
+            let ab = Box::new(searcher(config));
+            let rand = Box::new(RandomPlayer::new(seed + 1_000_000));
+            let (expected, mut gm) = if seed % 2 == 0 {
+                (Winner::Black, GameManager::new(ab, rand))
+            } else {
+                (Winner::White, GameManager::new(rand, ab))
+            };
+            assert_eq!(gm.playout().winner, expected, "seed {seed}");
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Seeded random playouts for tests: the positions the engines search and
+//! solve, and the games the notation and training code replay, all drawn
+//! the same way from `Xor128::from_seed(seed)`.
+
+use crate::reversi::bitboard::{legal_moves, Board, Mask};
+use crate::reversi::rand::Xor128;
+
+/// Where `random_position` stops playing.
+#[derive(Clone, Copy, Debug)]
+pub enum Stop {
+    /// After this many plies from the start, passes included.
+    Plies(usize),
+    /// With this many empty cells and a legal move for the side to move; a game
+    /// that ends or passes there is replayed with the generator as it is.
+    Empties(u32),
+}
+
+/// A uniformly random legal move for the side to move (`board.0`), None when
+/// it has to pass.
+pub fn random_move(rng: &mut Xor128, board: &Board) -> Option<Mask> {
+    let moves = legal_moves(board.0, board.1);
+    if moves == 0 {
+        return None;
+    }
+    let mut m = moves;
+    for _ in 0..rng.next() % moves.count_ones() {
+        m &= m - 1;
+    }
+    Some(m & m.wrapping_neg())
+}
+
+fn play(board: &Board, mov: Option<Mask>) -> Board {
+    match mov {
+        Some(mov) => board.flip(mov).switch(),
+        None => board.switch(),
+    }
+}
+
+fn empties(board: &Board) -> u32 {
+    64 - (board.0 | board.1).count_ones()
+}
+
+/// The position random moves from the start reach at `stop`, seen by its side
+/// to move.
+pub fn random_position(seed: u32, stop: Stop) -> Board {
+    let mut rng = Xor128::from_seed(seed);
+    match stop {
+        Stop::Plies(plies) => (0..plies).fold(Board::new(), |board, _| {
+            let mov = random_move(&mut rng, &board);
+            play(&board, mov)
+        }),
+        Stop::Empties(target) => loop {
+            let mut board = Board::new();
+            while empties(&board) > target && board.continues() {
+                let mov = random_move(&mut rng, &board);
+                board = play(&board, mov);
+            }
+            if empties(&board) == target && legal_moves(board.0, board.1) != 0 {
+                return board;
+            }
+        },
+    }
+}
+
+/// A random game from the start to its end.
+pub struct RandomGame {
+    /// Each position, seen by its side to move, with the move played there
+    /// (None for a pass).
+    pub plies: Vec<(Board, Option<Mask>)>,
+    /// The final position, seen by the side that would move next.
+    pub end: Board,
+}
+
+impl RandomGame {
+    /// The moves of the game, None for a pass.
+    pub fn moves(&self) -> Vec<Option<Mask>> {
+        self.plies.iter().map(|&(_, mov)| mov).collect()
+    }
+
+    /// Every position before the end, labelled with the final disk difference
+    /// for its side to move.
+    pub fn labelled(&self) -> Vec<(Board, i32)> {
+        let (me, opp) = self.end.count();
+        let mut score = me as i32 - opp as i32;
+        let mut samples = Vec::with_capacity(self.plies.len());
+        for (board, _) in self.plies.iter().rev() {
+            score = -score;
+            samples.push((board.clone(), score));
+        }
+        samples
+    }
+}
+
+/// The positions of `random_games(seed, games)`, each labelled with the final
+/// disk difference for its side to move.
+pub fn random_samples(seed: u32, games: usize) -> Vec<(Board, i32)> {
+    random_games(seed, games).iter().flat_map(RandomGame::labelled).collect()
+}
+
+/// `games` random games, one after another from the same generator.
+pub fn random_games(seed: u32, games: usize) -> Vec<RandomGame> {
+    let mut rng = Xor128::from_seed(seed);
+    (0..games)
+        .map(|_| {
+            let mut board = Board::new();
+            let mut plies = Vec::new();
+            while board.continues() {
+                let mov = random_move(&mut rng, &board);
+                let next = play(&board, mov);
+                plies.push((board, mov));
+                board = next;
+            }
+            RandomGame { plies, end: board }
+        })
+        .collect()
+}
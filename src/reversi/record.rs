@@ -0,0 +1,62 @@
+//! Game records: who played, where the game started and every ply with what
+//! the mover's engine thought of it. `GameManager` produces them
+//! (`GameResult::record`) and `ggf` reads and writes them, so games from other
+//! Othello software and our own go through the same replay and analysis code.
+
+use crate::reversi::bitboard::Mask;
+use crate::reversi::notation::Position;
+use crate::reversi::transcript::{Transcript, TranscriptError};
+
+/// One ply of a recorded game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveRecord {
+    /// The move, None for a pass.
+    pub mov: Option<Mask>,
+    /// The mover's evaluation of the position after its move, in disks from
+    /// its own side: the final disk difference when the engine solved it, or
+    /// the evaluation stored in the file.
+    pub eval: Option<f64>,
+    /// Seconds the mover spent on the move, if known.
+    pub time: Option<f64>,
+}
+
+impl MoveRecord {
+    /// A bare move without evaluation or time.
+    pub fn new(mov: Option<Mask>) -> MoveRecord {
+        MoveRecord { mov, eval: None, time: None }
+    }
+}
+
+/// A game as a start position and the moves played from it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub black: String,
+    pub white: String,
+    pub start: Position,
+    pub moves: Vec<MoveRecord>,
+    /// Final disk difference, black minus white, if the game has a result.
+    pub result: Option<i32>,
+}
+
+impl GameRecord {
+    /// A record without moves or result, starting at `start`.
+    pub fn new(black: &str, white: &str, start: Position) -> GameRecord {
+        GameRecord {
+            black: black.to_string(),
+            white: white.to_string(),
+            start,
+            moves: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// Replays the moves from the start, checking each one, for the positions
+    /// the game went through.
+    pub fn replay(&self) -> Result<Transcript, TranscriptError> {
+        let mut transcript = Transcript::new(&self.start);
+        for record in &self.moves {
+            transcript.push(record.mov)?;
+        }
+        Ok(transcript)
+    }
+}
@@ -2,19 +2,23 @@
 //! the web UI (see `web/src/index.ts`).
 //!
 //! `AlphaBeta5` plays itself from the opening down to a target number of empty
-//! cells, then the exact endgame solver ([`AlphaBeta5Player::solve_exact`]) is
-//! run from the resulting position. If the side to move has a *proven* forced win
-//! (exact final disk difference > 0), that position is returned; the human then
-//! plays it out from their (winning) turn against the engine.
+//! cells, then a win/loss/draw solve ([`AlphaBeta5Player::solve_wld`]) checks
+//! whether the side to move has a *proven* forced win. Only then is the exact
+//! endgame solver ([`AlphaBeta5Player::solve_exact`]) run for the winning
+//! margin, and the position returned; the human then plays it out from their
+//! (winning) turn against the engine.
 //!
-//! The board is kept in the engine's usual "side to move is `board.0`"
-//! perspective throughout, so one ply is `board.flip(mov).switch()` and the
-//! returned position's `.0` is exactly the side that is to move (the future
-//! human). This mirrors the manual driving pattern in `benches/duel5.rs`.
+//! The self-play runs through `GameManager`, stopped at the target empties;
+//! the puzzle is the position it stops at in the engine's usual "side to move
+//! is `board.0`" perspective, so `.0` is exactly the side that is to move (the
+//! future human).
 
-use crate::reversi::bitboard::{legal_moves, Board};
-use crate::reversi::player::alphabeta5::AlphaBeta5Player;
+use crate::reversi::bitboard::{legal_moves, Board, Mask};
+use crate::reversi::gm::GameManager;
+use crate::reversi::player::alphabeta5::{AlphaBeta5Player, Outcome};
 use crate::reversi::player::Player;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 /// A position where the side to move (`me`) has a proven forced win by `margin`
 /// disks under perfect play. `me`/`opp` are raw bitmasks from the mover's
@@ -61,8 +65,12 @@ pub fn generate_win_position(
 
         let mut solver = AlphaBeta5Player::with_budget(s, solve_budget);
         // A `None` here means the solve was aborted (budget exhausted): never
-        // trust it as a verdict. Only a completed solve with a positive margin is
-        // a proven forced win for the side to move.
+        // trust it as a verdict. Most positions are not wins, and proving that
+        // is far cheaper than an exact solve, which only the wins need for their
+        // margin (with the solve table already warm).
+        if solver.solve_wld(&view) != Some(Outcome::Win) {
+            continue;
+        }
         if let Some(margin) = solver.solve_exact(&view) {
             if margin > 0 {
                 return Some(WinPosition { me: view.0, opp: view.1, margin });
@@ -79,27 +87,28 @@ pub fn generate_win_position(
 fn play_to_empties(seed: u32, target_empties: u32, gen_budget: u64) -> Option<Board> {
     // One player instance drives both sides: `next()` clears its search table and
     // decays history every call and does not touch the solve table this far from
-    // the end, so sharing it is exactly its normal move-to-move behaviour.
-    let mut ai = AlphaBeta5Player::with_budget(seed, gen_budget);
-    let mut board = Board::new();
-    loop {
-        // Each move fills exactly one empty cell, so `empties` hits the target
-        // exactly (a pass does not change the board).
-        let empties = 64 - (board.0 | board.1).count_ones();
-        if empties == target_empties {
-            return Some(board);
-        }
-        if legal_moves(board.0, board.1) == 0 {
-            board = board.switch(); // current side must pass
-            if legal_moves(board.0, board.1) == 0 {
-                return None; // both sides pass: game over before the target
-            }
-            continue;
-        }
-        match ai.next(&board) {
-            Some(mov) => board = board.flip(mov).switch(), // play; opponent becomes .0
-            None => board = board.switch(),                // defensive; shouldn't happen
-        }
+    // the end, so sharing it is exactly its normal move-to-move behaviour, and
+    // only one set of tables is allocated per attempt.
+    let ai = Rc::new(RefCell::new(AlphaBeta5Player::with_budget(seed, gen_budget)));
+    let mut gm = GameManager::new(Box::new(Seat(Rc::clone(&ai))), Box::new(Seat(ai)));
+    gm.set_stop_empties(Some(target_empties));
+    // Each move fills exactly one empty cell, so a game that gets there stops
+    // at the target exactly.
+    let position = gm.playout().position();
+    let board = position.to_move();
+    (64 - (board.0 | board.1).count_ones() == target_empties).then_some(board)
+}
+
+/// One side of a game played by a shared engine.
+struct Seat(Rc<RefCell<AlphaBeta5Player>>);
+
+impl Player for Seat {
+    fn next(&mut self, board: &Board) -> Option<Mask> {
+        self.0.borrow_mut().next(board)
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.borrow().name()
     }
 }
 
@@ -131,6 +140,14 @@ mod tests {
         assert_eq!(64 - (win.me | win.opp).count_ones(), TARGET);
     }
 
+    // The self-play line, and so the puzzle, depends only on the seed.
+    #[test]
+    fn self_play_is_reproducible_per_seed() {
+        let first = play_to_empties(7, 30, 2_000).expect("reaches 30 empties");
+        assert_eq!(64 - (first.0 | first.1).count_ones(), 30);
+        assert_eq!(play_to_empties(7, 30, 2_000), Some(first));
+    }
+
     // Calibration (run explicitly): per-game to-move forced-win rate and solve
     // completion rate at each difficulty, to justify MAX_ATTEMPTS and estimate
     // generation time. Run with:
@@ -0,0 +1,277 @@
+//! Game transcripts: a whole game as one compact string of square names, e.g.
+//! `f5d6c3d3c4`, columns `a`-`h` left to right and rows `1`-`8` top to
+//! bottom (the names of `util::position_to_name`, lowercased).
+//!
+//! Passes are not written: a side without a legal move has to pass, so the
+//! reader puts them back. It also accepts an explicit `pa` where the side to
+//! move has to pass. Whitespace is ignored and upper case is accepted.
+//!
+//! Reading replays the moves from the start and checks every one of them; an
+//! illegal transcript is reported with the ply it breaks at and why.
+
+use crate::reversi::bitboard::{coordinate, flip_disks, legal_moves, position_to_mask, Mask};
+use crate::reversi::gm::Turn;
+use crate::reversi::notation::Position;
+use crate::reversi::util;
+use std::fmt;
+
+/// A replayed transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transcript {
+    /// One entry per ply, None for a pass, in the mover's single-bit form.
+    pub moves: Vec<Option<Mask>>,
+    /// The positions the game went through: the start, then the one after
+    /// each of `moves`.
+    pub positions: Vec<Position>,
+}
+
+/// Why a transcript could not be replayed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// The text is not a square name (or `pa`).
+    NotASquare(String),
+    /// The square already holds a disk.
+    Occupied(Mask),
+    /// The square is empty but flips no disk.
+    Illegal(Mask),
+    /// An explicit pass while the side to move has a legal move.
+    PassWithMoves,
+    /// A move after neither side can move any more.
+    GameOver,
+}
+
+/// Error replaying a transcript: `ply` (1-based, passes included) broke it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptError {
+    pub ply: usize,
+    pub reason: Reason,
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ply {}: ", self.ply)?;
+        match &self.reason {
+            Reason::NotASquare(text) => write!(f, "{text:?} is not a square"),
+            Reason::Occupied(mov) => write!(f, "{} is occupied", square_name(*mov)),
+            Reason::Illegal(mov) => write!(f, "{} flips no disk", square_name(*mov)),
+            Reason::PassWithMoves => write!(f, "pass while a move is legal"),
+            Reason::GameOver => write!(f, "move after the end of the game"),
+        }
+    }
+}
+
+impl std::error::Error for TranscriptError {}
+
+/// Lower-case name of the single-bit `mov`, e.g. `f5`.
+pub fn square_name(mov: Mask) -> String {
+    let (r, c) = coordinate(mov);
+    util::position_to_name(r, c).to_lowercase()
+}
+
+/// The square named `name` (`f5` or `F5`), if it is one.
+pub fn parse_square(name: &str) -> Option<Mask> {
+    let mut chars = name.chars();
+    let (col, row) = (chars.next()?.to_ascii_lowercase(), chars.next()?);
+    if chars.next().is_some() || !('a'..='h').contains(&col) || !('1'..='8').contains(&row) {
+        return None;
+    }
+    Some(position_to_mask(row as usize - '1' as usize, col as usize - 'a' as usize))
+}
+
+/// Writes `moves` (None for a pass) as a transcript; passes are left out.
+pub fn write(moves: &[Option<Mask>]) -> String {
+    moves.iter().flatten().map(|&mov| square_name(mov)).collect()
+}
+
+/// Writes `moves` one square per word with every pass as `pa`, e.g. a
+/// principal variation `f5 d6 pa c3`; `parse_from` reads it back from the
+/// position it starts at.
+pub fn write_line(moves: &[Option<Mask>]) -> String {
+    let names: Vec<String> = moves.iter().map(|mov| mov.map_or("pa".into(), square_name)).collect();
+    names.join(" ")
+}
+
+/// Replays `text` from the opening position.
+pub fn parse(text: &str) -> Result<Transcript, TranscriptError> {
+    parse_from(&Position::new(), text)
+}
+
+/// Replays `text` from `start`.
+pub fn parse_from(start: &Position, text: &str) -> Result<Transcript, TranscriptError> {
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut transcript = Transcript::new(start);
+    for token in chars.chunks(2) {
+        let token: String = token.iter().collect();
+        let mov = match parse_square(&token) {
+            Some(mov) => Some(mov),
+            None if token.eq_ignore_ascii_case("pa") => None,
+            None => return Err(transcript.error(Reason::NotASquare(token))),
+        };
+        match mov {
+            Some(mov) => transcript.play(mov)?,
+            None => transcript.push(None)?,
+        }
+    }
+    Ok(transcript)
+}
+
+impl Transcript {
+    /// An empty transcript starting at `start`.
+    pub fn new(start: &Position) -> Transcript {
+        Transcript { moves: Vec::new(), positions: vec![start.clone()] }
+    }
+
+    /// The last position.
+    pub fn position(&self) -> &Position {
+        self.positions.last().unwrap()
+    }
+
+    /// Plays `mov` (None for a pass) on the last position if it is legal there.
+    /// A pass is legal only when the side to move has no move.
+    pub fn push(&mut self, mov: Option<Mask>) -> Result<(), TranscriptError> {
+        let position = self.position();
+        let me = position.to_move();
+        if !me.continues() {
+            return Err(self.error(Reason::GameOver));
+        }
+        let after = match mov {
+            None if legal_moves(me.0, me.1) != 0 => {
+                return Err(self.error(Reason::PassWithMoves));
+            }
+            None => me,
+            Some(mov) if (me.0 | me.1) & mov != 0 => {
+                return Err(self.error(Reason::Occupied(mov)));
+            }
+            Some(mov) if flip_disks(me.0, me.1, mov) == 0 => {
+                return Err(self.error(Reason::Illegal(mov)));
+            }
+            Some(mov) => me.flip(mov),
+        };
+        let turn = position.turn;
+        let board = match turn {
+            Turn::Black => after,
+            Turn::White => after.switch(),
+        };
+        self.moves.push(mov);
+        self.positions.push(Position { board, turn: turn.switch() });
+        Ok(())
+    }
+
+    /// Plays `mov` like `push`, first passing for the side to move if it has
+    /// to, for move lists that leave passes out.
+    pub fn play(&mut self, mov: Mask) -> Result<(), TranscriptError> {
+        let me = self.position().to_move();
+        if legal_moves(me.0, me.1) == 0 && legal_moves(me.1, me.0) != 0 {
+            self.push(None)?;
+        }
+        self.push(Some(mov))
+    }
+
+    // An error at the next ply.
+    fn error(&self, reason: Reason) -> TranscriptError {
+        TranscriptError { ply: self.moves.len() + 1, reason }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::playout::random_games;
+
+    #[test]
+    fn squares_by_name() {
+        assert_eq!(parse_square("a1"), Some(1));
+        assert_eq!(parse_square("H8"), Some(1 << 63));
+        assert_eq!(parse_square("f5"), Some(position_to_mask(4, 5)));
+        for bad in ["i1", "a9", "a0", "a", "a10", "pa"] {
+            assert_eq!(parse_square(bad), None, "{bad}");
+        }
+        assert_eq!(square_name(position_to_mask(4, 5)), "f5");
+    }
+
+    #[test]
+    fn opening_moves() {
+        let transcript = parse("f5d6c3d3c4").unwrap();
+        assert_eq!(transcript.moves.len(), 5);
+        assert_eq!(transcript.positions.len(), 6);
+        assert_eq!(transcript.positions[0], Position::new());
+        let last = transcript.position();
+        assert_eq!(last.turn, Turn::White);
+        assert_eq!(last.board.count(), (6, 3));
+        assert_eq!(write(&transcript.moves), "f5d6c3d3c4");
+        assert_eq!(parse(" F5 D6\nC3 ").unwrap().moves, transcript.moves[..3]);
+    }
+
+    #[test]
+    fn random_games_round_trip_with_passes() {
+        let mut passes = 0;
+        for seed in 0..50 {
+            let moves = random_games(seed, 1)[0].moves();
+            passes += moves.iter().filter(|m| m.is_none()).count();
+            let text = write(&moves);
+            let transcript = parse(&text).unwrap();
+            // Passes at the end of a game are not in the text.
+            let played = moves.iter().rposition(|m| m.is_some()).unwrap() + 1;
+            assert_eq!(transcript.moves, moves[..played], "seed {seed}");
+            assert!(!transcript.position().board.continues());
+        }
+        assert!(passes > 0, "no game had a pass");
+    }
+
+    #[test]
+    fn explicit_pass() {
+        let (seed, moves) = (0..)
+            .map(|seed| (seed, random_games(seed, 1)[0].moves()))
+            .find(|(_, moves)| moves.iter().any(|m| m.is_none()))
+            .unwrap();
+        let pass = moves.iter().position(|m| m.is_none()).unwrap();
+        let text = format!("{}pa{}", write(&moves[..pass]), write(&moves[pass + 1..]));
+        assert_eq!(parse(&text).unwrap(), parse(&write(&moves)).unwrap(), "seed {seed}");
+
+        let err = parse("f5pa").unwrap_err();
+        assert_eq!(err, TranscriptError { ply: 2, reason: Reason::PassWithMoves });
+    }
+
+    #[test]
+    fn lines_write_their_passes() {
+        assert_eq!(write_line(&[parse_square("f5"), None, parse_square("c3")]), "f5 pa c3");
+        assert_eq!(write_line(&[]), "");
+        for seed in 0..20 {
+            let moves = random_games(seed, 1)[0].moves();
+            assert_eq!(parse(&write_line(&moves)).unwrap().moves, moves, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn errors_give_the_ply_and_reason() {
+        let cases = [
+            ("f5d6c3d3c9", 5, Reason::NotASquare("c9".to_string())),
+            ("f5d6c", 3, Reason::NotASquare("c".to_string())),
+            ("f5f5", 2, Reason::Occupied(position_to_mask(4, 5))),
+            ("f5a1", 2, Reason::Illegal(1)),
+        ];
+        for (text, ply, reason) in cases {
+            assert_eq!(parse(text), Err(TranscriptError { ply, reason }), "{text}");
+        }
+        assert_eq!(parse("f5a1").unwrap_err().to_string(), "ply 2: a1 flips no disk");
+
+        let moves = random_games(3, 1)[0].moves();
+        let full = write(&moves);
+        let played = parse(&full).unwrap();
+        let end = &played.position().board;
+        let free = (0..64).map(|i| 1u64 << i).find(|&m| (end.0 | end.1) & m == 0);
+        let extra = format!("{full}{}", free.map_or("a1".to_string(), square_name));
+        let err = parse(&extra).unwrap_err();
+        assert_eq!(err.reason, Reason::GameOver);
+        assert_eq!(err.ply, played.moves.len() + 1);
+    }
+
+    #[test]
+    fn from_another_start() {
+        let start: Position =
+            "---------------------------OX------XO--------------------------- O".parse().unwrap();
+        let transcript = parse_from(&start, "e3").unwrap();
+        assert_eq!(transcript.position().turn, Turn::Black);
+        assert_eq!(transcript.position().board.count(), (1, 4));
+    }
+}
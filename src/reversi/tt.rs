@@ -0,0 +1,285 @@
+//! A fixed-size, lock-free transposition table that one or several search
+//! threads can probe and store into.
+//!
+//! The table is preallocated to a power-of-two number of buckets, each one
+//! cache line holding two slots, so it never allocates or rehashes during a
+//! search and never has to be thrown away wholesale when it fills up. A slot is
+//! three `AtomicU64`s: the two key bitboards and a data word holding a 48-bit
+//! payload packed by the caller, the entry's depth and the age (search
+//! generation) it was stored in. A writer racing a reader (or another writer)
+//! can leave a slot torn, so the key words are stored xor-ed with the data word
+//! ("lockless hashing"): a reader only accepts a slot whose key words un-xor to
+//! exactly the position it asked for, which a torn slot practically never does.
+//! No hash collision can be mistaken for a hit, because the full
+//! `(black, white)` key is verified.
+//!
+//! Replacement is depth- and age-preferred: a store overwrites its own
+//! position's slot if the bucket has one, and otherwise evicts the slot left by
+//! an earlier search, or failing that the shallower one. `new_search` starts a
+//! new age, which also lets callers ignore entries that only held for an
+//! earlier search (`probe_fresh`) without clearing the table, except once
+//! every 256 searches, when the 8-bit age wraps.
+
+use crate::reversi::bitboard::Mask;
+use crate::reversi::hash::FxHasher;
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+/// Bits of the data word available to the caller's payload.
+pub const PAYLOAD_BITS: u32 = 48;
+const PAYLOAD_MASK: u64 = (1 << PAYLOAD_BITS) - 1;
+
+struct Slot {
+    black: AtomicU64, // black ^ word
+    white: AtomicU64, // white ^ word
+    word: AtomicU64,  // payload | depth << 48 | age << 56
+}
+
+// Two 24-byte slots padded to one cache line, so a probe touches one line.
+#[repr(align(64))]
+struct Bucket([Slot; 2]);
+
+/// Fixed-size table from `(black, white)` positions to a 48-bit payload and a
+/// depth. Every method takes `&self`, so it can be shared through an `Arc`
+/// between threads.
+pub struct TranspositionTable {
+    buckets: Box<[Bucket]>,
+    // `buckets.len() - 1`; the length is a power of two.
+    mask: usize,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// A table with room for at least `entries` positions (rounded up to a power
+    /// of two). 32 bytes per entry.
+    pub fn with_capacity(entries: usize) -> TranspositionTable {
+        TranspositionTable::with_buckets(entries.div_ceil(2).max(1).next_power_of_two())
+    }
+
+    /// The largest table that fits in `mb` MiB (at least one bucket).
+    pub fn with_size_mb(mb: usize) -> TranspositionTable {
+        let buckets = (mb << 20) / std::mem::size_of::<Bucket>();
+        TranspositionTable::with_buckets(if buckets == 0 { 1 } else { 1 << buckets.ilog2() })
+    }
+
+    fn with_buckets(len: usize) -> TranspositionTable {
+        let slot =
+            || Slot { black: AtomicU64::new(0), white: AtomicU64::new(0), word: AtomicU64::new(0) };
+        let buckets = (0..len).map(|_| Bucket([slot(), slot()])).collect();
+        TranspositionTable { buckets, mask: len - 1, age: AtomicU8::new(0) }
+    }
+
+    /// Number of slots.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * 2
+    }
+
+    /// Size of the slots in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.buckets.len() * std::mem::size_of::<Bucket>()
+    }
+
+    /// Starts a new age: entries stored from now on are preferred over older
+    /// ones, which `probe_fresh` no longer returns. The age is 8 bits, so when
+    /// it wraps the table is cleared rather than let entries 256 searches old
+    /// pass for fresh.
+    pub fn new_search(&self) {
+        if self.age.fetch_add(1, Ordering::Relaxed) == u8::MAX {
+            self.clear();
+        }
+    }
+
+    #[inline]
+    fn bucket(&self, black: Mask, white: Mask) -> &Bucket {
+        let mut h = FxHasher::default();
+        h.write_u64(black);
+        h.write_u64(white);
+        // The high bits of the multiply are the best mixed.
+        &self.buckets[(h.finish() >> 32) as usize & self.mask]
+    }
+
+    /// The slot holding the position and its data word, if any.
+    #[inline]
+    fn find(&self, black: Mask, white: Mask) -> Option<(&Slot, u64)> {
+        find_in(self.bucket(black, white), black, white)
+    }
+
+    /// The payload and depth stored for the position, whatever its age.
+    #[inline]
+    pub fn probe(&self, black: Mask, white: Mask) -> Option<(u64, u8)> {
+        self.find(black, white).map(|(_, word)| (word & PAYLOAD_MASK, depth_of(word)))
+    }
+
+    /// Like `probe`, but only entries stored since the last `new_search`.
+    #[inline]
+    pub fn probe_fresh(&self, black: Mask, white: Mask) -> Option<(u64, u8)> {
+        let age = self.age.load(Ordering::Relaxed);
+        self.find(black, white)
+            .filter(|&(_, word)| age_of(word) == age)
+            .map(|(_, word)| (word & PAYLOAD_MASK, depth_of(word)))
+    }
+
+    /// Stores `data` (at most `PAYLOAD_BITS` bits) for the position, which was
+    /// searched `depth` deep, replacing the least valuable slot of its bucket.
+    #[inline]
+    pub fn store(&self, black: Mask, white: Mask, depth: u8, data: u64) {
+        debug_assert_eq!(data & !PAYLOAD_MASK, 0, "payload wider than {PAYLOAD_BITS} bits");
+        let age = self.age.load(Ordering::Relaxed);
+        let bucket = self.bucket(black, white);
+        let slot = match find_in(bucket, black, white) {
+            Some((slot, _)) => slot,
+            None => {
+                // Older ages go first, then the shallower entry.
+                let value = |slot: &Slot| {
+                    let word = slot.word.load(Ordering::Relaxed);
+                    ((age_of(word) == age) as u32) << 8 | depth_of(word) as u32
+                };
+                let [a, b] = &bucket.0;
+                if value(b) < value(a) {
+                    b
+                } else {
+                    a
+                }
+            }
+        };
+        let word = data | (depth as u64) << 48 | (age as u64) << 56;
+        slot.word.store(word, Ordering::Relaxed);
+        slot.black.store(black ^ word, Ordering::Relaxed);
+        slot.white.store(white ^ word, Ordering::Relaxed);
+    }
+
+    /// Forgets the position, if the table holds it.
+    pub fn remove(&self, black: Mask, white: Mask) {
+        if let Some((slot, _)) = self.find(black, white) {
+            slot.black.store(0, Ordering::Relaxed);
+            slot.white.store(0, Ordering::Relaxed);
+            slot.word.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Empties every slot. Not meant to race with probes or stores.
+    pub fn clear(&self) {
+        for slot in self.buckets.iter().flat_map(|bucket| bucket.0.iter()) {
+            slot.black.store(0, Ordering::Relaxed);
+            slot.white.store(0, Ordering::Relaxed);
+            slot.word.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[inline]
+fn find_in(bucket: &Bucket, black: Mask, white: Mask) -> Option<(&Slot, u64)> {
+    bucket.0.iter().find_map(|slot| {
+        let word = slot.word.load(Ordering::Relaxed);
+        let hit = slot.black.load(Ordering::Relaxed) ^ word == black
+            && slot.white.load(Ordering::Relaxed) ^ word == white;
+        hit.then_some((slot, word))
+    })
+}
+
+#[inline]
+fn depth_of(word: u64) -> u8 {
+    (word >> 48) as u8
+}
+
+#[inline]
+fn age_of(word: u64) -> u8 {
+    (word >> 56) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn store_then_probe_round_trips() {
+        let t = TranspositionTable::with_capacity(1000);
+        assert_eq!(t.capacity(), 1024);
+        assert_eq!(t.probe(0b11, 0b100), None);
+        t.store(0b11, 0b100, 5, 42);
+        assert_eq!(t.probe(0b11, 0b100), Some((42, 5)));
+        // The colour-swapped position is a different key.
+        assert_eq!(t.probe(0b100, 0b11), None);
+        // Storing the same position again overwrites it.
+        t.store(0b11, 0b100, 2, 43);
+        assert_eq!(t.probe(0b11, 0b100), Some((43, 2)));
+        t.remove(0b11, 0b100);
+        assert_eq!(t.probe(0b11, 0b100), None);
+    }
+
+    #[test]
+    fn size_in_mb_is_respected() {
+        let t = TranspositionTable::with_size_mb(3);
+        assert_eq!(t.size_bytes(), 2 << 20);
+        assert_eq!(t.capacity(), (2 << 20) / 32);
+        assert_eq!(TranspositionTable::with_size_mb(0).capacity(), 2);
+    }
+
+    #[test]
+    fn shallow_and_old_entries_are_replaced_first() {
+        // With a single bucket every position collides.
+        let t = TranspositionTable::with_capacity(1);
+        t.store(1, 2, 9, 7);
+        t.store(3, 4, 3, 8);
+        t.store(5, 6, 4, 9); // evicts the shallower (3, 4)
+        assert_eq!(t.probe(1, 2), Some((7, 9)));
+        assert_eq!(t.probe(3, 4), None);
+        assert_eq!(t.probe(5, 6), Some((9, 4)));
+
+        // After a new search, old entries go first however deep they are, and
+        // are no longer fresh.
+        t.new_search();
+        assert_eq!(t.probe_fresh(1, 2), None);
+        assert_eq!(t.probe(1, 2), Some((7, 9)));
+        t.store(7, 8, 1, 10);
+        t.store(9, 10, 1, 11);
+        assert_eq!(t.probe(1, 2), None);
+        assert_eq!(t.probe(5, 6), None);
+        assert_eq!(t.probe_fresh(9, 10), Some((11, 1)));
+
+        t.clear();
+        assert_eq!(t.probe(9, 10), None);
+    }
+
+    #[test]
+    fn wrapping_age_clears_the_table() {
+        let t = TranspositionTable::with_capacity(16);
+        t.store(1, 2, 5, 7);
+        for _ in 0..255 {
+            t.new_search();
+        }
+        assert_eq!(t.probe(1, 2), Some((7, 5)));
+        assert_eq!(t.probe_fresh(1, 2), None);
+        // Back at the age it was stored in: an old entry must not look fresh.
+        t.new_search();
+        assert_eq!(t.probe_fresh(1, 2), None);
+        assert_eq!(t.probe(1, 2), None);
+    }
+
+    // Hammers a tiny table from several threads, each storing payloads that are
+    // a checkable function of their key. A torn slot must never be read as a hit.
+    #[test]
+    fn concurrent_stores_never_yield_a_foreign_payload() {
+        let payload = |black: u64, white: u64| (black.rotate_left(7) ^ white) & PAYLOAD_MASK;
+        let t = Arc::new(TranspositionTable::with_capacity(16));
+        std::thread::scope(|s| {
+            for id in 0..4u64 {
+                let t = Arc::clone(&t);
+                s.spawn(move || {
+                    for i in 0..20_000u64 {
+                        let black = (i * 4 + id) << 8;
+                        let white = !black & 0xFF;
+                        t.store(black, white, (i % 7) as u8, payload(black, white));
+                        // Probe a key this thread stored earlier (maybe since evicted).
+                        let pb = ((i / 2) * 4 + id) << 8;
+                        let pw = !pb & 0xFF;
+                        if let Some((data, _)) = t.probe(pb, pw) {
+                            assert_eq!(data, payload(pb, pw));
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,299 @@
+//! Texel-style tuning of the linear evaluation weights of `AlphaBeta4` and
+//! `AlphaBeta5`.
+//!
+//! Positions are collected from `AlphaBeta5` self-play and labelled with the
+//! final disk difference for their side to move. Once few enough cells are
+//! empty the rest of the game is solved exactly with `solve_exact`, so the
+//! labels are perfect-play results rather than whatever the engine happened to
+//! play. `tune` then fits the per-phase weights so that the evaluation, scaled
+//! to disks, predicts the labels with the least squared error.
+//!
+//! The scale from evaluation units to disks is fixed from the starting weights
+//! and only the weights move, so the tuned evaluation keeps the magnitude the
+//! search's windows and margins were set for. The result prints as a
+//! `PhaseWeights` literal to paste over the engine's defaults after review;
+//! `cargo bench --bench texel` runs the whole pipeline.
+
+use crate::reversi::bitboard::{legal_moves, Board};
+use crate::reversi::player::alphabeta5::AlphaBeta5Player;
+use crate::reversi::player::eval::{features, phase, PhaseWeights, Weights};
+use crate::reversi::player::Player;
+use crate::reversi::rand::Xor128;
+use std::fmt;
+
+/// A position (side to move = `board.0`) and the final disk difference for its
+/// side to move.
+#[derive(Clone)]
+pub struct Sample {
+    pub board: Board,
+    pub score: i32,
+}
+
+/// How to generate labelled positions by self-play.
+#[derive(Clone, Debug)]
+pub struct SelfPlay {
+    /// Number of games.
+    pub games: u32,
+    pub seed: u32,
+    /// Per-move node budget of the self-play engine.
+    pub budget: u64,
+    /// Opening plies played at random, so that games differ.
+    pub random_plies: usize,
+    /// Empty cells at which the game is solved exactly and stops.
+    pub solve_empties: u32,
+    /// Node budget for that solve. If it runs out the game is played on to
+    /// the end and labelled with the actual result.
+    pub solve_budget: u64,
+}
+
+impl Default for SelfPlay {
+    fn default() -> Self {
+        SelfPlay {
+            games: 200,
+            seed: 1,
+            budget: 20_000,
+            random_plies: 8,
+            solve_empties: 14,
+            solve_budget: 20_000_000,
+        }
+    }
+}
+
+impl SelfPlay {
+    /// Plays the games and returns every position where the side to move had
+    /// a move, each labelled with its game's result.
+    pub fn generate(&self) -> Vec<Sample> {
+        let mut rng = Xor128::from_seed(self.seed);
+        let mut samples = Vec::new();
+        for game in 0..self.games {
+            let seed = self.seed.wrapping_add(game).wrapping_mul(0x9E37_79B1);
+            let mut ai = AlphaBeta5Player::with_budget(seed, self.budget);
+            let mut solver = AlphaBeta5Player::with_budget(seed, self.solve_budget);
+            let mut board = Board::new();
+            let mut black_to_move = true;
+            // Positions with whether black was to move in them.
+            let mut line = Vec::new();
+            let mut passes = 0;
+            let mut solved = None;
+            while passes < 2 {
+                let legal = legal_moves(board.0, board.1);
+                if legal == 0 {
+                    passes += 1;
+                    board = board.switch();
+                    black_to_move = !black_to_move;
+                    continue;
+                }
+                passes = 0;
+                line.push((board.clone(), black_to_move));
+                if 64 - (board.0 | board.1).count_ones() <= self.solve_empties {
+                    if let Some(score) = solver.solve_exact(&board) {
+                        solved = Some(score);
+                        break;
+                    }
+                }
+                let mov = if line.len() <= self.random_plies {
+                    let mut m = legal;
+                    for _ in 0..rng.next() % legal.count_ones() {
+                        m &= m - 1;
+                    }
+                    m & m.wrapping_neg()
+                } else {
+                    ai.next(&board).expect("legal move exists")
+                };
+                board = board.flip(mov).switch();
+                black_to_move = !black_to_move;
+            }
+            // The result for whoever is to move in `board`, turned into black's.
+            let score = solved.unwrap_or_else(|| {
+                let (me, opp) = board.count();
+                me as i32 - opp as i32
+            });
+            let black_score = if black_to_move { score } else { -score };
+            samples.extend(line.into_iter().map(|(board, black)| Sample {
+                board,
+                score: if black { black_score } else { -black_score },
+            }));
+        }
+        samples
+    }
+}
+
+/// Engine-independent form of the per-phase linear weights: the `opening`,
+/// `midgame` and `endgame` sets, each in the field order of `Weights` (`pos`,
+/// `mob`, `pmob`, `front`, `stab`, `disk`). Prints as a `PhaseWeights` literal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinearWeights(pub [[i32; 6]; 3]);
+
+impl From<PhaseWeights> for LinearWeights {
+    fn from(w: PhaseWeights) -> Self {
+        let set = |w: Weights| [w.pos, w.mob, w.pmob, w.front, w.stab, w.disk];
+        LinearWeights([set(w.opening), set(w.midgame), set(w.endgame)])
+    }
+}
+
+impl From<LinearWeights> for PhaseWeights {
+    fn from(w: LinearWeights) -> Self {
+        let set = |[pos, mob, pmob, front, stab, disk]: [i32; 6]| Weights {
+            pos,
+            mob,
+            pmob,
+            front,
+            stab,
+            disk,
+        };
+        PhaseWeights { opening: set(w.0[0]), midgame: set(w.0[1]), endgame: set(w.0[2]) }
+    }
+}
+
+impl fmt::Display for LinearWeights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "PhaseWeights {{")?;
+        for (name, [pos, mob, pmob, front, stab, disk]) in
+            ["opening", "midgame", "endgame"].iter().zip(self.0)
+        {
+            writeln!(
+                f,
+                "    {name}: Weights {{ pos: {pos}, mob: {mob}, pmob: {pmob}, front: {front}, stab: {stab}, disk: {disk} }},"
+            )?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Outcome of `tune`.
+#[derive(Clone, Debug)]
+pub struct TuneReport {
+    pub weights: LinearWeights,
+    /// Disks per evaluation unit.
+    pub scale: f64,
+    /// Mean squared prediction error in disks², with the starting weights.
+    pub error_before: f64,
+    /// The same with the tuned weights.
+    pub error_after: f64,
+}
+
+/// Fits `start` to `samples` by `iterations` steps of gradient descent on the
+/// mean squared error between the scaled evaluation and the labels.
+/// The terms are `eval::features`, the ones the engines weigh. Game-over
+/// positions are skipped, since the engines score them outright.
+pub fn tune(samples: &[Sample], start: LinearWeights, iterations: usize) -> TuneReport {
+    let data: Vec<(usize, [f64; 6], f64)> = samples
+        .iter()
+        .filter(|s| legal_moves(s.board.0, s.board.1) | legal_moves(s.board.1, s.board.0) != 0)
+        .map(|s| {
+            // The engines' weight set for the position.
+            let phase = phase(64 - (s.board.0 | s.board.1).count_ones());
+            (phase, features(&s.board).map(f64::from), s.score as f64)
+        })
+        .collect();
+    let n = data.len().max(1) as f64;
+    let eval = |w: &[[f64; 6]; 3], (phase, x, _): &(usize, [f64; 6], f64)| -> f64 {
+        w[*phase].iter().zip(x).map(|(w, x)| w * x).sum()
+    };
+    let error = |w: &[[f64; 6]; 3], scale: f64| -> f64 {
+        data.iter().map(|d| (d.2 - scale * eval(w, d)).powi(2)).sum::<f64>() / n
+    };
+
+    let mut w = start.0.map(|set| set.map(f64::from));
+    // Least-squares scale for the starting weights; a guess if they say nothing.
+    let (ey, ee) = data.iter().fold((0.0, 0.0), |(ey, ee), d| {
+        let e = eval(&w, d);
+        (ey + e * d.2, ee + e * e)
+    });
+    let scale = if ee > 0.0 { ey / ee } else { 0.01 };
+    let error_before = error(&w, scale);
+
+    // Each weight's step is divided by its own curvature, so terms of very
+    // different magnitudes (the positional sum against a disk count) converge
+    // alike. Below 1/3 (two over the number of terms) this cannot diverge.
+    const RATE: f64 = 0.15;
+    let mut curvature = [[0.0; 6]; 3];
+    for (phase, x, _) in &data {
+        for (c, x) in curvature[*phase].iter_mut().zip(x) {
+            *c += scale * scale * x * x;
+        }
+    }
+    for _ in 0..iterations {
+        let mut gradient = [[0.0; 6]; 3];
+        for d in &data {
+            let residual = scale * eval(&w, d) - d.2;
+            for (g, x) in gradient[d.0].iter_mut().zip(d.1) {
+                *g += scale * residual * x;
+            }
+        }
+        for ((w, g), c) in
+            w.iter_mut().flatten().zip(gradient.iter().flatten()).zip(curvature.iter().flatten())
+        {
+            if *c > 0.0 {
+                *w -= RATE * g / c;
+            }
+        }
+    }
+
+    let weights = LinearWeights(w.map(|set| set.map(|w| w.round() as i32)));
+    let rounded = weights.0.map(|set| set.map(f64::from));
+    TuneReport { weights, scale, error_before, error_after: error(&rounded, scale) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::player::alphabeta5;
+    use crate::reversi::playout::random_samples;
+
+    #[test]
+    fn self_play_labels_end_with_an_exact_solve() {
+        let config = SelfPlay { games: 2, budget: 2_000, solve_empties: 10, ..SelfPlay::default() };
+        let samples = config.generate();
+        assert!(samples.len() >= 2 * 40, "{} samples", samples.len());
+        for sample in &samples {
+            assert_ne!(legal_moves(sample.board.0, sample.board.1), 0);
+            assert!(sample.score.abs() <= 64);
+        }
+        // The last position of the last game is the solved one, and the labels
+        // before it alternate with the side to move.
+        let last = samples.last().unwrap();
+        assert_eq!(64 - (last.board.0 | last.board.1).count_ones(), 10);
+        let mut solver = AlphaBeta5Player::with_budget(1, config.solve_budget);
+        assert_eq!(solver.solve_exact(&last.board), Some(last.score));
+        // The previous mover's disks all stay theirs; they are `.1` unless the
+        // opponent had to pass.
+        let previous = &samples[samples.len() - 2];
+        if previous.board.0 & !last.board.1 == 0 {
+            assert_eq!(previous.score, -last.score);
+        } else {
+            assert_eq!(previous.score, last.score);
+        }
+    }
+
+    #[test]
+    fn tuning_reduces_the_error() {
+        let samples: Vec<Sample> = random_samples(1, 60)
+            .into_iter()
+            .map(|(board, score)| Sample { board, score })
+            .collect();
+        let start = LinearWeights::from(alphabeta5::tuned_weights());
+        let report = tune(&samples, start, 30);
+        assert!(report.scale > 0.0);
+        assert!(report.error_after < report.error_before, "{report:?}");
+        assert_ne!(report.weights, start);
+
+        let start = LinearWeights::from(PhaseWeights::default());
+        let report = tune(&samples, start, 30);
+        assert!(report.error_after < report.error_before, "{report:?}");
+    }
+
+    #[test]
+    fn weights_convert_and_print_as_a_literal() {
+        let weights = LinearWeights::from(alphabeta5::tuned_weights());
+        assert_eq!(weights.0[0], [140, 20, 20, 35, 40, 0]);
+        let round_trip: PhaseWeights = weights.into();
+        assert_eq!(LinearWeights::from(round_trip), weights);
+        let printed = weights.to_string();
+        assert!(printed.starts_with("PhaseWeights {\n"));
+        assert!(printed.contains(
+            "    opening: Weights { pos: 140, mob: 20, pmob: 20, front: 35, stab: 40, disk: 0 },\n"
+        ));
+        assert!(printed.ends_with("},\n}"));
+    }
+}
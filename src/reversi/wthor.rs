@@ -0,0 +1,365 @@
+//! Reader of WTHOR databases (`.wtb`), the archive format of the French
+//! Othello federation's game base, for bulk import into the book builder and
+//! the tuners.
+//!
+//! A file is a 16-byte header followed by one 68-byte record per game, all
+//! little endian. The header holds the creation date (century, year, month,
+//! day: one byte each), the number of games (u32), the number of other records
+//! (u16), the year of the games (u16), the board size (0 or 8), the game type,
+//! the depth of the theoretical scores and a spare byte. A record holds the
+//! tournament, black and white players (u16 each), black's disks and
+//! black's theoretical disks (u8 each), then 60 move bytes.
+//!
+//! Players and tournaments are numbers into the `.JOU`/`.TRN` name files.
+//! The 60 move bytes are `10 * row + column` (`56` is f5), 1-based, padded
+//! with zeros; passes are not stored.
+//!
+//! Every game is replayed and checked while reading. A corrupt record is
+//! reported as an error of its own and reading goes on with the next one.
+
+use crate::reversi::bitboard::{position_to_mask, Mask};
+use crate::reversi::notation::Position;
+use crate::reversi::record::{GameRecord, MoveRecord};
+use crate::reversi::transcript::{Transcript, TranscriptError};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+const HEADER_LEN: usize = 16;
+const RECORD_LEN: usize = 68;
+
+/// The file header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// Creation date (year, month, day).
+    pub created: (u16, u8, u8),
+    /// Number of game records.
+    pub games: u32,
+    /// Year the games were played.
+    pub year: u16,
+    /// Depth of the perfect play behind `WthorGame::theoretical`.
+    pub depth: u8,
+}
+
+/// A game read from a record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WthorGame {
+    pub tournament: u16,
+    pub black: u16,
+    pub white: u16,
+    /// Black's disks at the end, empties counted to the winner.
+    pub black_disks: u8,
+    /// Black's disks with perfect play from `Header::depth` empties.
+    pub theoretical: u8,
+    /// Every ply, None for a (forced) pass.
+    pub moves: Vec<Option<Mask>>,
+}
+
+impl WthorGame {
+    /// Final disk difference, black minus white.
+    pub fn black_score(&self) -> i32 {
+        2 * self.black_disks as i32 - 64
+    }
+
+    /// The game as a record, players named by their numbers.
+    pub fn record(&self) -> GameRecord {
+        let mut record =
+            GameRecord::new(&self.black.to_string(), &self.white.to_string(), Position::new());
+        record.moves = self.moves.iter().map(|&mov| MoveRecord::new(mov)).collect();
+        record.result = Some(self.black_score());
+        record
+    }
+}
+
+/// What is wrong with one game record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordError {
+    /// Move byte `index` (0-based) is not a square.
+    NotASquare { index: usize, byte: u8 },
+    /// The moves do not replay from the opening.
+    Illegal(TranscriptError),
+    /// The file ends inside the record.
+    Truncated,
+}
+
+/// Error reading a WTHOR file.
+#[derive(Debug)]
+pub enum WthorError {
+    Io(io::Error),
+    /// The file is too short for a header or not of 8x8 games.
+    Header(String),
+    /// Game `index` (0-based) is corrupt.
+    Record {
+        index: usize,
+        error: RecordError,
+    },
+}
+
+impl fmt::Display for WthorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WthorError::Io(e) => write!(f, "wthor i/o error: {e}"),
+            WthorError::Header(reason) => write!(f, "wthor header: {reason}"),
+            WthorError::Record { index, error } => {
+                write!(f, "wthor game {index}: ")?;
+                match error {
+                    RecordError::NotASquare { index, byte } => {
+                        write!(f, "move {index} is not a square ({byte})")
+                    }
+                    RecordError::Illegal(e) => write!(f, "{e}"),
+                    RecordError::Truncated => write!(f, "record is truncated"),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for WthorError {}
+
+impl From<io::Error> for WthorError {
+    fn from(e: io::Error) -> Self {
+        WthorError::Io(e)
+    }
+}
+
+/// Streams the games of a WTHOR file, one `Result` per record.
+pub struct WthorReader<R> {
+    reader: R,
+    header: Header,
+    // Index of the next record.
+    index: usize,
+    // Set at an i/o error or the end of the file, after which nothing is read.
+    done: bool,
+}
+
+impl WthorReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WthorError> {
+        WthorReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> WthorReader<R> {
+    /// Reads the header off `reader`.
+    pub fn new(mut reader: R) -> Result<Self, WthorError> {
+        let mut bytes = [0; HEADER_LEN];
+        if !read_full(&mut reader, &mut bytes)? {
+            return Err(WthorError::Header("file is shorter than a header".to_string()));
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let board_size = bytes[12];
+        if board_size != 0 && board_size != 8 {
+            return Err(WthorError::Header(format!("board size {board_size} is not 8")));
+        }
+        let header = Header {
+            created: (bytes[0] as u16 * 100 + bytes[1] as u16, bytes[2], bytes[3]),
+            games: u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            year: u16_at(10),
+            depth: bytes[14],
+        };
+        Ok(WthorReader { reader, header, index: 0, done: false })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+}
+
+impl<R: Read> Iterator for WthorReader<R> {
+    type Item = Result<WthorGame, WthorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.index >= self.header.games as usize {
+            return None;
+        }
+        let index = self.index;
+        self.index += 1;
+        let mut bytes = [0; RECORD_LEN];
+        let error = match read_full(&mut self.reader, &mut bytes) {
+            Ok(true) => match parse_record(&bytes) {
+                Ok(game) => return Some(Ok(game)),
+                Err(error) => error,
+            },
+            Ok(false) => {
+                self.done = true;
+                RecordError::Truncated
+            }
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e.into()));
+            }
+        };
+        Some(Err(WthorError::Record { index, error }))
+    }
+}
+
+// Fills `buf`; false if the input ends first.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => return Ok(false),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+fn parse_record(bytes: &[u8; RECORD_LEN]) -> Result<WthorGame, RecordError> {
+    let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+    let mut transcript = Transcript::new(&Position::new());
+    for (index, &byte) in bytes[8..].iter().enumerate() {
+        if byte == 0 {
+            break;
+        }
+        let (row, col) = (byte / 10, byte % 10);
+        if !(1..=8).contains(&row) || !(1..=8).contains(&col) {
+            return Err(RecordError::NotASquare { index, byte });
+        }
+        let mov = position_to_mask(row as usize - 1, col as usize - 1);
+        transcript.play(mov).map_err(RecordError::Illegal)?;
+    }
+    Ok(WthorGame {
+        tournament: u16_at(0),
+        black: u16_at(2),
+        white: u16_at(4),
+        black_disks: bytes[6],
+        theoretical: bytes[7],
+        moves: transcript.moves,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reversi::bitboard::coordinate;
+    use crate::reversi::gm::GameManager;
+    use crate::reversi::player::random::RandomPlayer;
+    use crate::reversi::transcript::{self, Reason};
+
+    fn header(games: u32) -> Vec<u8> {
+        let mut bytes = vec![20, 24, 3, 17];
+        bytes.extend(games.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        bytes.extend(2023u16.to_le_bytes());
+        bytes.extend([0, 0, 22, 0]);
+        bytes
+    }
+
+    fn record(tournament: u16, black: u16, white: u16, disks: u8, moves: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(tournament.to_le_bytes());
+        bytes.extend(black.to_le_bytes());
+        bytes.extend(white.to_le_bytes());
+        bytes.extend([disks, disks]);
+        bytes.extend(moves);
+        bytes.resize(RECORD_LEN, 0);
+        bytes
+    }
+
+    // The move bytes of `moves` (passes left out).
+    fn move_bytes(moves: &[Option<Mask>]) -> Vec<u8> {
+        let bytes = moves.iter().flatten().map(|&mov| coordinate(mov));
+        bytes.map(|(r, c)| (10 * (r + 1) + c + 1) as u8).collect()
+    }
+
+    #[test]
+    fn reads_header_and_games() {
+        let mut file = header(12);
+        let mut games = Vec::new();
+        for seed in 0..12 {
+            let mut gm = GameManager::new(
+                Box::new(RandomPlayer::new(seed)),
+                Box::new(RandomPlayer::new(seed + 50)),
+            );
+            let result = gm.playout();
+            let moves: Vec<_> = result.plies.iter().map(|ply| ply.mov).collect();
+            let (black, white) = result.disks;
+            // Empties go to the winner.
+            let disks = match black.cmp(&white) {
+                std::cmp::Ordering::Greater => 64 - white,
+                std::cmp::Ordering::Equal => 32,
+                std::cmp::Ordering::Less => black,
+            } as u8;
+            file.extend(record(7, seed as u16, 100 + seed as u16, disks, &move_bytes(&moves)));
+            games.push((moves, disks));
+        }
+
+        let mut reader = WthorReader::new(&file[..]).unwrap();
+        assert_eq!(
+            *reader.header(),
+            Header { created: (2024, 3, 17), games: 12, year: 2023, depth: 22 }
+        );
+        for (seed, (moves, disks)) in games.into_iter().enumerate() {
+            let game = reader.next().unwrap().unwrap();
+            assert_eq!(
+                (game.tournament, game.black, game.white),
+                (7, seed as u16, 100 + seed as u16)
+            );
+            // Passes at the end of a game are not in the file.
+            let played = moves.iter().rposition(|m| m.is_some()).unwrap() + 1;
+            assert_eq!(game.moves, moves[..played], "seed {seed}");
+            assert_eq!(game.black_disks, disks);
+            assert!(game.record().replay().is_ok());
+        }
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn opening_bytes() {
+        // f5 d6 c3 d3 c4
+        let file = [header(1), record(0, 0, 0, 32, &[56, 64, 33, 34, 43])].concat();
+        let game = WthorReader::new(&file[..]).unwrap().next().unwrap().unwrap();
+        assert_eq!(game.moves, transcript::parse("f5d6c3d3c4").unwrap().moves);
+        assert_eq!(game.black_score(), 0);
+        assert_eq!(game.record().result, Some(0));
+    }
+
+    #[test]
+    fn corrupt_records_do_not_stop_the_file() {
+        let file = [
+            header(5),
+            record(0, 0, 0, 32, &[56, 69]),
+            record(0, 0, 0, 32, &[56, 11]),
+            record(0, 1, 0, 32, &[56]),
+            record(0, 0, 0, 32, &[56, 56]),
+            record(0, 2, 0, 32, &[56])[..30].to_vec(),
+        ]
+        .concat();
+        let results: Vec<_> = WthorReader::new(&file[..]).unwrap().collect();
+        assert_eq!(results.len(), 5);
+        let errors: Vec<_> = results
+            .iter()
+            .map(|r| match r {
+                Ok(game) => Ok(game.black),
+                Err(WthorError::Record { index, error }) => Err((*index, error.clone())),
+                Err(e) => panic!("{e}"),
+            })
+            .collect();
+        let illegal = |ply, reason| RecordError::Illegal(TranscriptError { ply, reason });
+        assert_eq!(
+            errors,
+            [
+                Err((0, RecordError::NotASquare { index: 1, byte: 69 })),
+                Err((1, illegal(2, Reason::Illegal(1)))),
+                Ok(1),
+                Err((3, illegal(2, Reason::Occupied(position_to_mask(4, 5))))),
+                Err((4, RecordError::Truncated)),
+            ]
+        );
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "wthor game 0: move 1 is not a square (69)"
+        );
+    }
+
+    #[test]
+    fn bad_headers() {
+        assert!(matches!(WthorReader::new(&header(0)[..10]), Err(WthorError::Header(_))));
+        let mut ten = header(0);
+        ten[12] = 10;
+        assert!(matches!(WthorReader::new(&ten[..]), Err(WthorError::Header(_))));
+    }
+}
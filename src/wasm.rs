@@ -9,7 +9,14 @@
 //! through pointers into linear memory. The full board is two `u64`s, so the
 //! JS side keeps `(black, white)` as BigInts and reconstructs the next board
 //! from the flip mask returned by [`flip_mask`].
+//!
+//! The one exception is text: position strings (see `notation`) go through a
+//! fixed buffer in linear memory whose address [`text_buffer`] returns. JS
+//! writes a string there and calls [`parse_position`], or calls
+//! [`format_position`] and reads the string back.
 use crate::reversi::bitboard::Board;
+use crate::reversi::gm::Turn;
+use crate::reversi::notation::Position;
 use crate::reversi::player::best::{get_best_ai_player, BestAiPlayer};
 use crate::reversi::player::Player;
 use crate::reversi::sprint::generate_win_position;
@@ -32,8 +39,16 @@ thread_local! {
     // the mover's perspective (`me` = side to move = human). Read back through the
     // getters below, since each extern fn can only return a single u64.
     static GENERATED: RefCell<Option<(u64, u64, i32)>> = const { RefCell::new(None) };
+
+    // The text buffer shared with JS, and the position the last successful
+    // `parse_position` read from it: (black, white, white to move).
+    static TEXT: RefCell<[u8; TEXT_CAPACITY]> = const { RefCell::new([0; TEXT_CAPACITY]) };
+    static PARSED: RefCell<(u64, u64, bool)> = const { RefCell::new((0, 0, false)) };
 }
 
+// Bytes in the text buffer: a position string is 66, an error message shorter.
+const TEXT_CAPACITY: usize = 128;
+
 /// Mask of cells where the black (to-move) player may put a disk.
 #[no_mangle]
 pub extern "C" fn valid_moves(black: u64, white: u64) -> u64 {
@@ -112,3 +127,103 @@ pub extern "C" fn generated_white() -> u64 {
 pub extern "C" fn generated_margin() -> u64 {
     GENERATED.with(|cell| cell.borrow().map(|(_, _, m)| m as i64 as u64).unwrap_or(0))
 }
+
+/// Address of the text buffer ([`TEXT_CAPACITY`] bytes) in linear memory.
+#[no_mangle]
+pub extern "C" fn text_buffer() -> u64 {
+    TEXT.with(|cell| cell.borrow_mut().as_mut_ptr() as usize as u64)
+}
+
+// Copies `text` into the text buffer, truncated to fit at a character
+// boundary so the buffer stays valid UTF-8, and returns its length.
+fn write_text(text: &str) -> u64 {
+    TEXT.with(|cell| {
+        let mut buf = cell.borrow_mut();
+        let len = text
+            .char_indices()
+            .map(|(i, c)| i + c.len_utf8())
+            .take_while(|&end| end <= TEXT_CAPACITY)
+            .last()
+            .unwrap_or(0);
+        buf[..len].copy_from_slice(&text.as_bytes()[..len]);
+        len as u64
+    })
+}
+
+/// Parses the first `len` bytes of the text buffer as a position string. On
+/// success returns 0 and stashes the position for [`parsed_black`],
+/// [`parsed_white`] and [`parsed_white_to_move`]; on error writes the message
+/// into the buffer and returns its length.
+#[no_mangle]
+pub extern "C" fn parse_position(len: u32) -> u64 {
+    let parsed = TEXT.with(|cell| {
+        let buf = cell.borrow();
+        let bytes = &buf[..(len as usize).min(TEXT_CAPACITY)];
+        match std::str::from_utf8(bytes) {
+            Ok(text) => text.parse::<Position>().map_err(|e| e.to_string()),
+            Err(_) => Err("position is not valid UTF-8".to_string()),
+        }
+    });
+    match parsed {
+        Ok(position) => {
+            let Board(black, white) = position.board;
+            PARSED.with(|cell| *cell.borrow_mut() = (black, white, position.turn == Turn::White));
+            0
+        }
+        Err(message) => write_text(&message),
+    }
+}
+
+/// Black's disks of the last successful [`parse_position`].
+#[no_mangle]
+pub extern "C" fn parsed_black() -> u64 {
+    PARSED.with(|cell| cell.borrow().0)
+}
+
+/// White's disks of the last successful [`parse_position`].
+#[no_mangle]
+pub extern "C" fn parsed_white() -> u64 {
+    PARSED.with(|cell| cell.borrow().1)
+}
+
+/// 1 if white is to move in the last successful [`parse_position`], else 0.
+#[no_mangle]
+pub extern "C" fn parsed_white_to_move() -> u64 {
+    PARSED.with(|cell| cell.borrow().2 as u64)
+}
+
+/// Writes the position string of `(black, white)` with white to move iff.
+/// `white_to_move` is non-zero into the text buffer and returns its length.
+#[no_mangle]
+pub extern "C" fn format_position(black: u64, white: u64, white_to_move: u32) -> u64 {
+    let turn = if white_to_move != 0 { Turn::White } else { Turn::Black };
+    write_text(&Position { board: Board(black, white), turn }.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_text_round_trip() {
+        let Board(black, white) = Board::new();
+        let len = format_position(black, white, 1);
+        assert_eq!(len, 66);
+        assert_eq!(parse_position(len as u32), 0);
+        assert_eq!((parsed_black(), parsed_white(), parsed_white_to_move()), (black, white, 1));
+
+        // Errors come back through the buffer.
+        let len = parse_position(10);
+        let message = TEXT.with(|cell| String::from_utf8(cell.borrow()[..len as usize].to_vec()));
+        assert_eq!(message.unwrap(), "board has only 10 of 64 cells");
+    }
+
+    #[test]
+    fn long_text_is_cut_between_characters() {
+        let text = format!("a{}", "é".repeat(100));
+        let len = write_text(&text) as usize;
+        assert_eq!(len, TEXT_CAPACITY - 1);
+        let written = TEXT.with(|cell| String::from_utf8(cell.borrow()[..len].to_vec()));
+        assert_eq!(written.unwrap(), text[..len]);
+    }
+}